@@ -0,0 +1,285 @@
+use move_binary_format::{
+    file_format::{AbilitySet, DatatypeHandleIndex, FieldDefinition, SignatureToken},
+    CompiledModule,
+};
+use serde::Serialize;
+
+use crate::{visibility_label, VisibilityFilter};
+
+// The `--format abi` schema. Unlike `--format json`, which mirrors the tree and names types the
+// way it prints them, every type here is structured and fully qualified, so that SDK code
+// generators can map it to their own types without parsing anything.
+
+/// The ABI of a package
+#[derive(Serialize)]
+pub struct Package {
+    pub package_name: String,
+    /// The package's path relative to the scanned directory, absent for the directory itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relative_path: Option<String>,
+    /// Sorted by name
+    pub modules: Vec<Module>,
+}
+
+#[derive(Serialize)]
+pub struct Module {
+    /// The address the module is published at, as a `0x` literal (`0x0` when it is not)
+    pub address: String,
+    pub name: String,
+    /// The functions selected by `--visibility`, sorted by name
+    pub functions: Vec<Function>,
+    /// The structs and enums the module defines, sorted by name
+    pub types: Vec<Datatype>,
+}
+
+#[derive(Serialize)]
+pub struct Function {
+    pub name: String,
+    /// `public`, `friend` or `private`
+    pub visibility: &'static str,
+    pub entry: bool,
+    /// In order, referred to by index from `type_param` types
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<Type>,
+    pub returns: Vec<Type>,
+}
+
+#[derive(Serialize)]
+pub struct TypeParam {
+    /// The abilities a type argument must have, among `copy`, `drop`, `store` and `key`
+    pub constraints: Vec<String>,
+    /// Whether the parameter is phantom, i.e. not used by any field (only for datatypes)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub phantom: bool,
+}
+
+#[derive(Serialize)]
+pub struct Datatype {
+    /// `struct` or `enum`
+    pub kind: &'static str,
+    pub name: String,
+    pub abilities: Vec<String>,
+    pub type_params: Vec<TypeParam>,
+    /// The fields of a struct, in declaration order (empty for an enum)
+    pub fields: Vec<Field>,
+    /// The variants of an enum, in declaration order (absent for a struct)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+}
+
+#[derive(Serialize)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+/// Fields keep their names from bytecode: those of positional structs are `pos0`, `pos1`, ...
+#[derive(Serialize)]
+pub struct Field {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: Type,
+}
+
+/// A type, tagged with its `kind`: `bool`, `u8` to `u256`, `address` and `signer` have nothing
+/// else, the other kinds have the fields below
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Type {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Vector {
+        element: Box<Type>,
+    },
+    Reference {
+        mutable: bool,
+        to: Box<Type>,
+    },
+    /// The type parameter at `index` of the enclosing function or datatype
+    TypeParam {
+        index: u16,
+    },
+    /// A struct with `key`, i.e. a Sui object
+    Object(DatatypeRef),
+    /// Any other struct, or an enum
+    Datatype(DatatypeRef),
+}
+
+#[derive(Serialize)]
+pub struct DatatypeRef {
+    /// The address of the defining module, as a `0x` literal
+    pub address: String,
+    pub module: String,
+    pub name: String,
+    pub type_args: Vec<Type>,
+}
+
+/// The ABI of each of `modules`, sorted by name
+pub fn modules<'a>(
+    modules: impl Iterator<Item = &'a CompiledModule>,
+    visibility: VisibilityFilter,
+) -> Vec<Module> {
+    let mut modules = modules
+        .map(|compiled| module(compiled, visibility))
+        .collect::<Vec<_>>();
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    modules
+}
+
+/// The ABI of `module`, with the functions `visibility` selects
+fn module(module: &CompiledModule, visibility: VisibilityFilter) -> Module {
+    let mut functions = module
+        .function_defs()
+        .iter()
+        .filter(|def| visibility.includes(def.visibility))
+        .map(|def| {
+            let handle = module.function_handle_at(def.function);
+            Function {
+                name: module.identifier_at(handle.name).to_string(),
+                visibility: visibility_label(def.visibility),
+                entry: def.is_entry,
+                type_params: handle
+                    .type_parameters
+                    .iter()
+                    .map(|constraints| type_param(*constraints, false))
+                    .collect(),
+                params: types(module, &module.signature_at(handle.parameters).0),
+                returns: types(module, &module.signature_at(handle.return_).0),
+            }
+        })
+        .collect::<Vec<_>>();
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let structs = module.struct_defs().iter().map(|def| Datatype {
+        kind: "struct",
+        fields: fields(module, def.fields().into_iter().flatten()),
+        variants: Vec::new(),
+        ..datatype(module, def.struct_handle)
+    });
+    let enums = module.enum_defs().iter().map(|def| Datatype {
+        kind: "enum",
+        fields: Vec::new(),
+        variants: def
+            .variants
+            .iter()
+            .map(|variant| Variant {
+                name: module.identifier_at(variant.variant_name).to_string(),
+                fields: fields(module, variant.fields.iter()),
+            })
+            .collect(),
+        ..datatype(module, def.enum_handle)
+    });
+    let mut datatypes = structs.chain(enums).collect::<Vec<_>>();
+    datatypes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Module {
+        address: module.self_id().address().to_hex_literal(),
+        name: module.name().to_string(),
+        functions,
+        types: datatypes,
+    }
+}
+
+/// The parts of a `Datatype` that come from its handle
+fn datatype(module: &CompiledModule, handle: DatatypeHandleIndex) -> Datatype {
+    let handle = module.datatype_handle_at(handle);
+    Datatype {
+        kind: "struct",
+        name: module.identifier_at(handle.name).to_string(),
+        abilities: abilities(handle.abilities),
+        type_params: handle
+            .type_parameters
+            .iter()
+            .map(|param| type_param(param.constraints, param.is_phantom))
+            .collect(),
+        fields: Vec::new(),
+        variants: Vec::new(),
+    }
+}
+
+fn type_param(constraints: AbilitySet, phantom: bool) -> TypeParam {
+    TypeParam {
+        constraints: abilities(constraints),
+        phantom,
+    }
+}
+
+fn abilities(abilities: AbilitySet) -> Vec<String> {
+    abilities
+        .into_iter()
+        .map(|ability| ability.to_string())
+        .collect()
+}
+
+fn fields<'a>(
+    module: &CompiledModule,
+    fields: impl Iterator<Item = &'a FieldDefinition>,
+) -> Vec<Field> {
+    fields
+        .map(|field| Field {
+            name: module.identifier_at(field.name).to_string(),
+            type_: type_(module, &field.signature.0),
+        })
+        .collect()
+}
+
+fn types(module: &CompiledModule, tokens: &[SignatureToken]) -> Vec<Type> {
+    tokens.iter().map(|token| type_(module, token)).collect()
+}
+
+fn type_(module: &CompiledModule, token: &SignatureToken) -> Type {
+    match token {
+        SignatureToken::Bool => Type::Bool,
+        SignatureToken::U8 => Type::U8,
+        SignatureToken::U16 => Type::U16,
+        SignatureToken::U32 => Type::U32,
+        SignatureToken::U64 => Type::U64,
+        SignatureToken::U128 => Type::U128,
+        SignatureToken::U256 => Type::U256,
+        SignatureToken::Address => Type::Address,
+        SignatureToken::Signer => Type::Signer,
+        SignatureToken::Vector(inner) => Type::Vector {
+            element: Box::new(type_(module, inner)),
+        },
+        SignatureToken::Reference(inner) => Type::Reference {
+            mutable: false,
+            to: Box::new(type_(module, inner)),
+        },
+        SignatureToken::MutableReference(inner) => Type::Reference {
+            mutable: true,
+            to: Box::new(type_(module, inner)),
+        },
+        SignatureToken::TypeParameter(index) => Type::TypeParam { index: *index },
+        SignatureToken::Datatype(handle) => datatype_ref(module, *handle, &[]),
+        SignatureToken::DatatypeInstantiation(inner) => datatype_ref(module, inner.0, &inner.1),
+    }
+}
+
+fn datatype_ref(
+    module: &CompiledModule,
+    handle: DatatypeHandleIndex,
+    type_args: &[SignatureToken],
+) -> Type {
+    let handle = module.datatype_handle_at(handle);
+    let defining_module = module.module_handle_at(handle.module);
+    let reference = DatatypeRef {
+        address: module
+            .address_identifier_at(defining_module.address)
+            .to_hex_literal(),
+        module: module.identifier_at(defining_module.name).to_string(),
+        name: module.identifier_at(handle.name).to_string(),
+        type_args: types(module, type_args),
+    };
+    if handle.abilities.has_key() {
+        Type::Object(reference)
+    } else {
+        Type::Datatype(reference)
+    }
+}
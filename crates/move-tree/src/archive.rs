@@ -0,0 +1,51 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use tempfile::TempDir;
+
+/// Replace each path of `paths` that is an archive with the temporary directory it is extracted
+/// to. The directories are removed when the returned `TempDir`s are dropped, including those
+/// already extracted when a later archive fails.
+pub fn extract_all(paths: &mut [PathBuf]) -> Result<Vec<TempDir>> {
+    let mut extracted = Vec::new();
+    for path in paths.iter_mut().filter(|path| is_archive(path)) {
+        let dir = extract(path)?;
+        *path = dir.path().to_path_buf();
+        extracted.push(dir);
+    }
+    Ok(extracted)
+}
+
+/// Whether `path` is a `.tar.gz` (or `.tgz`) or `.zip` file, going by its name
+fn is_archive(path: &Path) -> bool {
+    path.is_file() && (is_tarball(path) || path.extension().is_some_and(|ext| ext == "zip"))
+}
+
+fn is_tarball(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Extract `path` into a new temporary directory. Neither format writes entries that would land
+/// outside of it, such as `../x`.
+fn extract(path: &Path) -> Result<TempDir> {
+    let dir = tempfile::Builder::new()
+        .prefix("move-tree-")
+        .tempdir()
+        .context("Failed to create a temporary directory")?;
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    if is_tarball(path) {
+        tar::Archive::new(GzDecoder::new(file)).unpack(dir.path())
+    } else {
+        zip::ZipArchive::new(file)
+            .and_then(|mut archive| archive.extract(dir.path()))
+            .map_err(Into::into)
+    }
+    .with_context(|| format!("Failed to extract {}", path.display()))?;
+    Ok(dir)
+}
@@ -0,0 +1,152 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use walkdir::WalkDir;
+
+/// A compiled package read from a build output directory
+pub struct BuiltPackage {
+    pub name: String,
+    /// The address of the package's modules, `0x0` when it is not published
+    pub id: AccountAddress,
+    /// The root package it was built as a dependency of, or `None` for a root package
+    pub dependency_of: Option<String>,
+    pub modules: Vec<CompiledModule>,
+}
+
+/// Every package under the build output directories `paths`: each `<Name>/bytecode_modules`
+/// directory holds the modules of root package `Name`, and its `dependencies/<Dep>` directories
+/// those of its dependencies. A package found more than once (as a dependency of several roots,
+/// or in several build directories) is kept once, by id, or by name when it is unpublished.
+/// Root packages come first, each group sorted by name.
+pub fn read_packages(paths: &[PathBuf]) -> Result<Vec<BuiltPackage>> {
+    let mut packages = BTreeMap::new();
+
+    for path in paths {
+        for entry in WalkDir::new(path).follow_links(false) {
+            let entry = entry?;
+            if !entry.file_type().is_dir() || entry.file_name() != "bytecode_modules" {
+                continue;
+            }
+            let Some(root_name) = entry
+                .path()
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+
+            add_package(&mut packages, &root_name, None, entry.path())?;
+
+            let dependencies = entry.path().join("dependencies");
+            if !dependencies.is_dir() {
+                continue;
+            }
+            let mut dep_dirs = fs::read_dir(&dependencies)
+                .with_context(|| format!("Failed to read {}", dependencies.display()))?
+                .map(|dep| dep.map(|dep| dep.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            dep_dirs.sort();
+            for dep_dir in dep_dirs.iter().filter(|dir| dir.is_dir()) {
+                let dep_name = dep_dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                add_package(&mut packages, &dep_name, Some(&root_name), dep_dir)?;
+            }
+        }
+    }
+
+    let mut packages = packages.into_values().collect::<Vec<_>>();
+    packages.sort_by(|a, b| {
+        (a.dependency_of.is_some(), &a.name).cmp(&(b.dependency_of.is_some(), &b.name))
+    });
+    Ok(packages)
+}
+
+/// Read the `.mv` files directly in `dir` as package `name`, unless the package was already seen.
+/// A root package replaces a dependency of the same id, so that packages are shown as roots when
+/// they are one anywhere.
+fn add_package(
+    packages: &mut BTreeMap<(AccountAddress, String), BuiltPackage>,
+    name: &str,
+    dependency_of: Option<&str>,
+    dir: &Path,
+) -> Result<()> {
+    let modules = read_modules(dir)?;
+    let Some(id) = modules.first().map(|module| *module.self_id().address()) else {
+        return Ok(());
+    };
+    let key = if id == AccountAddress::ZERO {
+        (id, name.to_string())
+    } else {
+        (id, String::new())
+    };
+
+    if let Some(existing) = packages.get(&key) {
+        if existing.dependency_of.is_none() || dependency_of.is_some() {
+            return Ok(());
+        }
+    }
+    packages.insert(
+        key,
+        BuiltPackage {
+            name: name.to_string(),
+            id,
+            dependency_of: dependency_of.map(str::to_string),
+            modules,
+        },
+    );
+    Ok(())
+}
+
+/// Whether `path` holds loose modules rather than packages: a `.mv` file, or a directory with
+/// `.mv` files directly in it and no `Move.toml`
+pub fn is_module_set(path: &Path) -> bool {
+    if path.is_file() {
+        return is_module_file(path);
+    }
+    path.is_dir()
+        && !path.join("Move.toml").exists()
+        && fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|entry| is_module_file(&entry.path()))
+}
+
+/// The modules of a path for which `is_module_set` holds
+pub fn read_module_set(path: &Path) -> Result<Vec<CompiledModule>> {
+    if path.is_file() {
+        Ok(vec![read_module(path)?])
+    } else {
+        read_modules(path)
+    }
+}
+
+fn read_modules(dir: &Path) -> Result<Vec<CompiledModule>> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|path| is_module_file(path));
+    paths.sort();
+
+    paths.iter().map(|path| read_module(path)).collect()
+}
+
+fn is_module_file(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext == "mv")
+}
+
+fn read_module(path: &Path) -> Result<CompiledModule> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    CompiledModule::deserialize_with_defaults(&bytes)
+        .with_context(|| format!("Failed to deserialize module {}", path.display()))
+}
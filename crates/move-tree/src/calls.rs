@@ -0,0 +1,46 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::{
+    file_format::{Bytecode, FunctionDefinition},
+    CompiledModule,
+};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+
+/// A function, identified by its module and name
+pub type FunctionId = (ModuleId, Identifier);
+
+/// The functions `function_def` calls, once each however many times it calls them
+pub fn callees(module: &CompiledModule, function_def: &FunctionDefinition) -> BTreeSet<FunctionId> {
+    let Some(code) = &function_def.code else {
+        return BTreeSet::new();
+    };
+
+    code.code
+        .iter()
+        .filter_map(|instruction| {
+            let handle = match instruction {
+                Bytecode::Call(handle) => *handle,
+                Bytecode::CallGeneric(inst) => module.function_instantiation_at(*inst).handle,
+                _ => return None,
+            };
+            let handle = module.function_handle_at(handle);
+            let callee = module.module_id_for_handle(module.module_handle_at(handle.module));
+            Some((callee, module.identifier_at(handle.name).to_owned()))
+        })
+        .collect()
+}
+
+/// How many functions of `modules` call each function. Functions nothing calls are left out.
+pub fn caller_counts<'a>(
+    modules: impl IntoIterator<Item = &'a CompiledModule>,
+) -> BTreeMap<FunctionId, usize> {
+    let mut counts = BTreeMap::new();
+    for module in modules {
+        for function_def in module.function_defs() {
+            for callee in callees(module, function_def) {
+                *counts.entry(callee).or_default() += 1;
+            }
+        }
+    }
+    counts
+}
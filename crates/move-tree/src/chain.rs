@@ -0,0 +1,57 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use move_binary_format::CompiledModule;
+use sui_sdk::{
+    rpc_types::{SuiObjectDataOptions, SuiRawData},
+    types::base_types::ObjectID,
+    SuiClientBuilder,
+};
+
+/// Fullnode used for chain lookups when `--rpc-url` is not given
+pub const DEFAULT_RPC_URL: &str = sui_sdk::SUI_MAINNET_URL;
+
+/// Returns true if `arg` has the shape of a package id (`0x` followed by up to 64 hex digits)
+pub fn looks_like_address(arg: &str) -> bool {
+    let Some(hex) = arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) else {
+        return false;
+    };
+
+    !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Fetch the package object `package_id` from the fullnode at `rpc_url` and deserialize its
+/// modules
+pub async fn fetch_package_modules(rpc_url: &str, package_id: &str) -> Result<Vec<CompiledModule>> {
+    let object_id = ObjectID::from_str(package_id)
+        .with_context(|| format!("Invalid package id `{}`", package_id))?;
+    let client = SuiClientBuilder::default()
+        .build(rpc_url)
+        .await
+        .with_context(|| format!("Failed to connect to {}", rpc_url))?;
+    let response = client
+        .read_api()
+        .get_object_with_options(object_id, SuiObjectDataOptions::new().with_bcs())
+        .await
+        .with_context(|| format!("Failed to fetch package {} from {}", object_id, rpc_url))?;
+    let object = response
+        .into_object()
+        .with_context(|| format!("Failed to fetch package {} from {}", object_id, rpc_url))?;
+
+    let Some(SuiRawData::Package(package)) = object.bcs else {
+        bail!("Object {} is not a Move package", object_id);
+    };
+
+    package
+        .module_map
+        .iter()
+        .map(|(name, bytes)| {
+            CompiledModule::deserialize_with_defaults(bytes).with_context(|| {
+                format!(
+                    "Failed to deserialize module `{}` of package {}",
+                    name, object_id
+                )
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,102 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::{FunctionInfo, ModuleInfo};
+
+/// An expectation file for `--entry-signature-compat`: the entry functions an SDK's bindings
+/// were generated for, with types named the way the tree names them (so under the same
+/// `--qualify-external` and `--always-qualify` flags):
+///
+/// ```json
+/// {"entry_functions": [
+///     {"module": "pool", "name": "swap", "type_params": ["T0"],
+///      "params": ["&mut Pool<T0>", "u64", "&mut TxContext"]}
+/// ]}
+/// ```
+#[derive(Deserialize)]
+pub struct Expectations {
+    pub entry_functions: Vec<ExpectedEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct ExpectedEntry {
+    pub module: String,
+    pub name: String,
+    #[serde(default)]
+    pub type_params: Vec<String>,
+    pub params: Vec<String>,
+    #[serde(default)]
+    pub returns: Vec<String>,
+}
+
+impl ExpectedEntry {
+    pub fn signature(&self) -> String {
+        signature(&self.type_params, &self.params, &self.returns)
+    }
+}
+
+/// How the package differs from an expected entry function
+pub enum Mismatch<'a> {
+    Missing(&'a ExpectedEntry),
+    /// The function exists but is no longer `entry`
+    NotEntry(&'a ExpectedEntry),
+    Changed {
+        expected: &'a ExpectedEntry,
+        found: &'a FunctionInfo,
+    },
+}
+
+pub fn read(path: &Path) -> Result<Expectations> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// The expected entry functions that `modules` (collected with every visibility) do not match,
+/// in the order of the expectation file
+pub fn check<'a>(expectations: &'a Expectations, modules: &'a [ModuleInfo]) -> Vec<Mismatch<'a>> {
+    expectations
+        .entry_functions
+        .iter()
+        .filter_map(|expected| {
+            let found = modules
+                .iter()
+                .find(|module| module.name == expected.module)
+                .and_then(|module| {
+                    module
+                        .functions
+                        .iter()
+                        .find(|function| function.name == expected.name)
+                });
+            match found {
+                None => Some(Mismatch::Missing(expected)),
+                Some(found) if !found.is_entry => Some(Mismatch::NotEntry(expected)),
+                Some(found)
+                    if found.type_params != expected.type_params
+                        || found.params != expected.params
+                        || found.returns != expected.returns =>
+                {
+                    Some(Mismatch::Changed { expected, found })
+                }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+/// `<T0>(u64, &mut TxContext): ()`
+pub fn signature(type_params: &[String], params: &[String], returns: &[String]) -> String {
+    let type_params = if type_params.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", type_params.join(", "))
+    };
+    let returns = match returns {
+        [] => "()".to_string(),
+        [single] => single.clone(),
+        several => format!("({})", several.join(", ")),
+    };
+    format!("{}({}): {}", type_params, params.join(", "), returns)
+}
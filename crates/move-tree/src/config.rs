@@ -0,0 +1,105 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// The file profiles are read from, in the current directory or the closest of its ancestors
+pub const FILE_NAME: &str = ".move-tree.toml";
+
+/// `args` with the flags of profile `name` inserted right after the subcommand, so that the flags
+/// given on the command line come later and override them. A profile is a `[profiles.NAME]` table
+/// whose keys are flag names: `structs = true` stands for `--structs`, `visibility = "all"` for
+/// `--visibility all`, and arrays repeat the flag.
+pub fn with_profile(mut args: Vec<OsString>, name: &str) -> Result<Vec<OsString>> {
+    let cwd = std::env::current_dir().context("Failed to read the current directory")?;
+    let Some(path) = find_config(&cwd) else {
+        bail!(
+            "`--config-profile {}` needs a {} in {} or one of its parents",
+            name,
+            FILE_NAME,
+            cwd.display()
+        );
+    };
+
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let config = toml::from_str::<toml::Value>(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let profiles = config.get("profiles").and_then(toml::Value::as_table);
+    let Some(profile) = profiles.and_then(|profiles| profiles.get(name)) else {
+        let known = profiles
+            .map(|profiles| profiles.keys().cloned().collect::<Vec<_>>().join(", "))
+            .unwrap_or_default();
+        bail!(
+            "no profile `{}` in {} (profiles: {})",
+            name,
+            path.display(),
+            if known.is_empty() { "none" } else { &known }
+        );
+    };
+    let Some(profile) = profile.as_table() else {
+        bail!("profile `{}` in {} is not a table", name, path.display());
+    };
+
+    let mut flags = Vec::new();
+    for (key, value) in profile {
+        let flag = format!("--{}", key.replace('_', "-"));
+        let values = match value {
+            toml::Value::Array(values) => values.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => flags.push(OsString::from(&flag)),
+                toml::Value::Boolean(false) => {}
+                toml::Value::String(value) => {
+                    flags.push(OsString::from(&flag));
+                    flags.push(OsString::from(value));
+                }
+                toml::Value::Integer(value) => {
+                    flags.push(OsString::from(&flag));
+                    flags.push(OsString::from(value.to_string()));
+                }
+                _ => bail!(
+                    "`{}` in profile `{}` of {} must be a boolean, a string, an integer or an \
+                     array of them",
+                    key,
+                    name,
+                    path.display()
+                ),
+            }
+        }
+    }
+
+    let position = subcommand_position(&args).map_or(args.len(), |position| position + 1);
+    args.splice(position..position, flags);
+    Ok(args)
+}
+
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The index of the subcommand in `args`: the first argument (after the program name) that is
+/// neither a flag nor the value of `--config-profile`
+fn subcommand_position(args: &[OsString]) -> Option<usize> {
+    let mut takes_value = false;
+    for (index, arg) in args.iter().enumerate().skip(1) {
+        if takes_value {
+            takes_value = false;
+            continue;
+        }
+        let arg = arg.to_string_lossy();
+        if arg == "--config-profile" {
+            takes_value = true;
+        } else if !arg.starts_with('-') {
+            return Some(index);
+        }
+    }
+    None
+}
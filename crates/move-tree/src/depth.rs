@@ -0,0 +1,126 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    graph_cache, load_graph_snapshot, package_path_label, palette, post_order,
+    render_dependency_label, render_package_header, sorted_deps, Branches, DependencyOrder,
+    DepsArgs, DevDependencies,
+};
+
+/// The longest dependency chain of a package in `deps --depth-report`
+#[derive(Serialize)]
+struct DepthReport {
+    package_name: String,
+    relative_path: Option<String>,
+    /// The number of edges in `chain`
+    depth: usize,
+    /// The dependencies along the chain, from the package's direct dependency down to a package
+    /// without dependencies
+    chain: Vec<String>,
+}
+
+/// The longest dependency chain of each of `package_roots`, for `--depth-report`
+pub async fn print_reports(args: &DepsArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut reports = Vec::new();
+    for root in package_roots {
+        let graph = load_graph_snapshot(root, &args.common.build, args.no_cache).await?;
+        let dev = DevDependencies::of(root, &graph, args).await?;
+        let order = DependencyOrder::new(args.sort_deps, &graph, &dev);
+
+        let chain = longest_chain(&graph, &dev, &order);
+        reports.push(DepthReport {
+            package_name: graph.name.clone(),
+            relative_path: package_path_label(&args.common, root),
+            depth: chain.len(),
+            chain,
+        });
+    }
+
+    if let Some(format) = args.format.data_format(args.common.json_pretty) {
+        return format.print_all(&reports);
+    }
+
+    let branches = Branches::from_args(&args.common);
+    let mut first = true;
+    for report in &reports {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let mut line = render_package_header(
+            "depth",
+            report.relative_path.as_deref(),
+            &report.package_name,
+            None,
+        );
+        line.push_str(&format!(
+            " {}",
+            format!("max depth {}", report.depth).bold()
+        ));
+        println!("{}", line);
+
+        if report.chain.is_empty() {
+            println!("{}{}", branches.last, "(no dependencies)".dimmed());
+        }
+        let mut prefix = String::new();
+        for dep in &report.chain {
+            println!(
+                "{}{}{} {}",
+                prefix,
+                branches.last,
+                palette().dependency.paint("dep").bold(),
+                palette().dependency.paint(dep)
+            );
+            prefix.push_str(branches.indent(true));
+        }
+    }
+
+    Ok(())
+}
+
+/// The labels of the dependencies along the longest chain below the root of `graph`. Among
+/// chains of the same length, the first one in `order` wins.
+fn longest_chain(
+    graph: &graph_cache::Graph,
+    dev: &DevDependencies,
+    order: &DependencyOrder,
+) -> Vec<String> {
+    let packages = post_order(
+        [graph.root()],
+        |info| {
+            sorted_deps(graph, info, dev, order)
+                .into_iter()
+                .map(|(_, dep_info)| dep_info)
+                .collect()
+        },
+        |info| info.id.clone(),
+    );
+
+    // The longest chain below each package by id, computed once those of its dependencies are
+    let mut chains: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for info in packages {
+        let mut longest = Vec::new();
+        for (dep_name, dep_info) in sorted_deps(graph, info, dev, order) {
+            let below = chains
+                .get(dep_info.id.as_str())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            if longest.is_empty() || below.len() + 1 > longest.len() {
+                longest = std::iter::once(render_dependency_label(
+                    dep_name,
+                    &dep_info.name,
+                    &dep_info.id,
+                ))
+                .chain(below.iter().cloned())
+                .collect();
+            }
+        }
+        chains.insert(info.id.as_str(), longest);
+    }
+
+    chains.remove(graph.root_id.as_str()).unwrap_or_default()
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use move_binary_format::file_format::Visibility;
+
+use crate::{FunctionInfo, ModuleInfo, StructInfo};
+
+/// How one function or datatype differs between two versions of a package
+pub enum Change<'a, T> {
+    Added(&'a T),
+    Removed(&'a T),
+    /// Present in both versions with a different signature (or layout, for datatypes)
+    Changed {
+        old: &'a T,
+        new: &'a T,
+    },
+}
+
+pub type FunctionChange<'a> = Change<'a, FunctionInfo>;
+pub type StructChange<'a> = Change<'a, StructInfo>;
+
+/// What two versions of a package are matched up by
+pub trait Item {
+    fn name(&self) -> &str;
+    /// Whether the two versions are compatible, i.e. not reported as changed
+    fn same(&self, other: &Self) -> bool;
+}
+
+impl Item for FunctionInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn same(&self, other: &Self) -> bool {
+        same_signature(self, other)
+    }
+}
+
+impl Item for StructInfo {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl<T: Item> Change<'_, T> {
+    pub fn name(&self) -> &str {
+        match self {
+            Change::Added(item) | Change::Removed(item) => item.name(),
+            Change::Changed { new, .. } => new.name(),
+        }
+    }
+}
+
+pub struct ModuleDiff<'a> {
+    pub name: &'a str,
+    pub changes: Vec<FunctionChange<'a>>,
+    /// Only ever non-empty when the datatypes of both versions were collected
+    pub struct_changes: Vec<StructChange<'a>>,
+}
+
+/// Compare the functions (and datatypes, when collected) of two versions of a package, module by
+/// module. Only modules with at least one change are returned, sorted by name, with their changes
+/// sorted by name.
+pub fn diff_modules<'a>(
+    old: &'a [ModuleInfo],
+    new: &'a [ModuleInfo],
+    only_changed_sigs: bool,
+) -> Vec<ModuleDiff<'a>> {
+    let mut paired: BTreeMap<&str, (Option<&ModuleInfo>, Option<&ModuleInfo>)> = BTreeMap::new();
+    for module in old {
+        paired.entry(&module.name).or_default().0 = Some(module);
+    }
+    for module in new {
+        paired.entry(&module.name).or_default().1 = Some(module);
+    }
+
+    paired
+        .into_iter()
+        .filter_map(|(name, (old, new))| {
+            let changes = diff_items(
+                old.map(|module| module.functions.as_slice()),
+                new.map(|module| module.functions.as_slice()),
+                only_changed_sigs,
+            );
+            let struct_changes = diff_items(
+                old.map(|module| module.structs.as_slice()),
+                new.map(|module| module.structs.as_slice()),
+                only_changed_sigs,
+            );
+
+            if changes.is_empty() && struct_changes.is_empty() {
+                return None;
+            }
+            Some(ModuleDiff {
+                name,
+                changes,
+                struct_changes,
+            })
+        })
+        .collect()
+}
+
+fn diff_items<'a, T: Item>(
+    old: Option<&'a [T]>,
+    new: Option<&'a [T]>,
+    only_changed_sigs: bool,
+) -> Vec<Change<'a, T>> {
+    let old_items = items_by_name(old);
+    let new_items = items_by_name(new);
+
+    let mut changes = Vec::new();
+    for (item_name, old_item) in &old_items {
+        match new_items.get(item_name) {
+            Some(new_item) if !old_item.same(new_item) => {
+                changes.push(Change::Changed {
+                    old: *old_item,
+                    new: *new_item,
+                });
+            }
+            Some(_) => {}
+            None if !only_changed_sigs => {
+                changes.push(Change::Removed(*old_item));
+            }
+            None => {}
+        }
+    }
+    if !only_changed_sigs {
+        for (item_name, new_item) in &new_items {
+            if !old_items.contains_key(item_name) {
+                changes.push(Change::Added(*new_item));
+            }
+        }
+    }
+
+    changes.sort_by(|a, b| a.name().cmp(b.name()));
+    changes
+}
+
+fn items_by_name<T: Item>(items: Option<&[T]>) -> BTreeMap<&str, &T> {
+    items
+        .into_iter()
+        .flatten()
+        .map(|item| (item.name(), item))
+        .collect()
+}
+
+fn same_signature(old: &FunctionInfo, new: &FunctionInfo) -> bool {
+    old.visibility == new.visibility
+        && old.type_params == new.type_params
+        && old.params == new.params
+        && old.returns == new.returns
+}
+
+/// A function present in both versions of a package with a different visibility
+pub struct VisibilityChange<'a> {
+    pub function: &'a str,
+    pub old: Visibility,
+    pub new: Visibility,
+}
+
+impl VisibilityChange<'_> {
+    /// Whether the new visibility is narrower, so that some callers of the old version can no
+    /// longer call it
+    pub fn is_breaking(&self) -> bool {
+        reach(self.new) < reach(self.old)
+    }
+}
+
+pub struct ModuleVisibilityChanges<'a> {
+    pub name: &'a str,
+    pub changes: Vec<VisibilityChange<'a>>,
+}
+
+/// The functions whose visibility differs between two versions of a package, both collected with
+/// every visibility so that a function moving in or out of `--visibility` is not taken for added
+/// or removed. Only modules with a change are returned, modules and functions sorted by name.
+pub fn visibility_changes<'a>(
+    old: &'a [ModuleInfo],
+    new: &'a [ModuleInfo],
+) -> Vec<ModuleVisibilityChanges<'a>> {
+    let new_modules = items_by_module(new);
+    let mut modules = old
+        .iter()
+        .filter_map(|old_module| {
+            let new_functions =
+                items_by_name(Some(new_modules.get(old_module.name.as_str()).copied()?));
+            let mut changes = old_module
+                .functions
+                .iter()
+                .filter_map(|old_function| {
+                    let new_function = new_functions.get(old_function.name.as_str())?;
+                    (old_function.visibility != new_function.visibility).then_some(
+                        VisibilityChange {
+                            function: &old_function.name,
+                            old: old_function.visibility,
+                            new: new_function.visibility,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            changes.sort_by(|a, b| a.function.cmp(b.function));
+            (!changes.is_empty()).then_some(ModuleVisibilityChanges {
+                name: &old_module.name,
+                changes,
+            })
+        })
+        .collect::<Vec<_>>();
+    modules.sort_by(|a, b| a.name.cmp(b.name));
+    modules
+}
+
+fn items_by_module(modules: &[ModuleInfo]) -> BTreeMap<&str, &[FunctionInfo]> {
+    modules
+        .iter()
+        .map(|module| (module.name.as_str(), module.functions.as_slice()))
+        .collect()
+}
+
+/// How widely a function of each visibility can be called
+fn reach(visibility: Visibility) -> u8 {
+    match visibility {
+        Visibility::Private => 0,
+        Visibility::Friend => 1,
+        Visibility::Public => 2,
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::{
+    file_format::{DatatypeHandleIndex, SignatureToken},
+    CompiledModule,
+};
+use move_core_types::account_address::AccountAddress;
+
+use crate::format_signature_token;
+
+/// A node in an expanded type: a datatype, or one of its fields
+pub struct TypeTree {
+    pub label: String,
+    pub children: Vec<TypeTree>,
+}
+
+/// Identifies a datatype across modules: defining address, module name and type name
+pub type DatatypeKey = (AccountAddress, String, String);
+
+struct Datatype<'a> {
+    module: &'a CompiledModule,
+    kind: &'static str,
+    name: String,
+    /// Field names (`Variant.field` for enums) and types
+    fields: Vec<(String, &'a SignatureToken)>,
+}
+
+/// The datatypes that can be expanded, i.e. those defined by the modules it was built from
+pub struct DatatypeIndex<'a> {
+    datatypes: BTreeMap<DatatypeKey, Datatype<'a>>,
+    max_depth: Option<usize>,
+}
+
+impl<'a> DatatypeIndex<'a> {
+    pub fn new(modules: &[&'a CompiledModule]) -> Self {
+        let mut datatypes = BTreeMap::new();
+
+        for &module in modules {
+            for def in module.struct_defs() {
+                let fields = def
+                    .fields()
+                    .into_iter()
+                    .flatten()
+                    .map(|field| {
+                        let name = module.identifier_at(field.name).to_string();
+                        (name, &field.signature.0)
+                    })
+                    .collect();
+                let (key, name) = datatype_key(module, def.struct_handle);
+                datatypes.insert(
+                    key,
+                    Datatype {
+                        module,
+                        kind: "struct",
+                        name,
+                        fields,
+                    },
+                );
+            }
+
+            for def in module.enum_defs() {
+                let fields = def
+                    .variants
+                    .iter()
+                    .flat_map(|variant| {
+                        let variant_name = module.identifier_at(variant.variant_name);
+                        variant.fields.iter().map(move |field| {
+                            let name =
+                                format!("{}.{}", variant_name, module.identifier_at(field.name));
+                            (name, &field.signature.0)
+                        })
+                    })
+                    .collect();
+                let (key, name) = datatype_key(module, def.enum_handle);
+                datatypes.insert(
+                    key,
+                    Datatype {
+                        module,
+                        kind: "enum",
+                        name,
+                        fields,
+                    },
+                );
+            }
+        }
+
+        DatatypeIndex {
+            datatypes,
+            max_depth: None,
+        }
+    }
+
+    /// Expand at most `max_depth` levels of datatypes below each root, rendering the datatypes
+    /// met deeper as `Name …`. `None` expands everything.
+    pub fn with_max_depth(self, max_depth: Option<usize>) -> Self {
+        DatatypeIndex { max_depth, ..self }
+    }
+
+    /// Expand every datatype defined in `module`, in name order
+    pub fn expand_module(&self, module: &CompiledModule) -> Vec<TypeTree> {
+        let address = *module.address();
+        let module_name = module.name().to_string();
+
+        let mut trees = self
+            .datatypes
+            .iter()
+            .filter(|((def_address, def_module, _), _)| {
+                *def_address == address && *def_module == module_name
+            })
+            .map(|(key, datatype)| {
+                let label = format!("{} {}", datatype.kind, datatype.name);
+                self.expand(key, datatype, label)
+            })
+            .collect::<Vec<_>>();
+
+        trees.sort_by(|a, b| a.label.cmp(&b.label));
+        trees
+    }
+
+    /// The tree of `datatype`, labelled `label`. Each field is expanded into the datatypes its type
+    /// mentions. `visited` holds the types on the path from the root, so a type that refers back to
+    /// one of them (directly, or through a type argument such as `Table<ID, Node>`) is rendered as
+    /// `<...>` instead of being expanded again. Its length is also the depth the `max_depth` limit
+    /// applies to. The tree is built with an explicit stack, as a chain of datatypes can be deeper
+    /// than the native one allows.
+    fn expand<'s>(
+        &'s self,
+        key: &DatatypeKey,
+        datatype: &'s Datatype<'a>,
+        label: String,
+    ) -> TypeTree {
+        let mut visited = BTreeSet::from([key.clone()]);
+        let mut stack = vec![Expansion::new(key.clone(), datatype, label)];
+        loop {
+            let expansion = stack
+                .last_mut()
+                .expect("the root is only popped once expanded");
+            let Some(field) = &mut expansion.field else {
+                if let Some((name, token)) = expansion.fields.next() {
+                    let module = expansion.module;
+                    expansion.field = Some(FieldExpansion {
+                        label: format!("{}: {}", name, format_signature_token(module, token)),
+                        mentioned: mentioned_datatypes(module, token).into_iter(),
+                        children: Vec::new(),
+                    });
+                    continue;
+                }
+
+                let done = stack.pop().expect("the expansion just looked at");
+                visited.remove(&done.key);
+                let tree = TypeTree {
+                    label: done.label,
+                    children: done.children,
+                };
+                match stack.last_mut().and_then(|parent| parent.field.as_mut()) {
+                    Some(parent) => parent.children.push(tree),
+                    None => return tree,
+                }
+                continue;
+            };
+
+            let Some((key, type_name)) = field.mentioned.next() else {
+                let field = expansion.field.take().expect("the field just looked at");
+                expansion.children.push(TypeTree {
+                    label: field.label,
+                    children: field.children,
+                });
+                continue;
+            };
+            let Some(target) = self.datatypes.get(&key) else {
+                continue;
+            };
+            if visited.contains(&key) {
+                field.children.push(TypeTree {
+                    label: format!("{} <...>", type_name),
+                    children: vec![],
+                });
+            } else if self.max_depth.is_some_and(|depth| visited.len() > depth) {
+                field.children.push(TypeTree {
+                    label: format!("{} …", type_name),
+                    children: vec![],
+                });
+            } else {
+                visited.insert(key.clone());
+                stack.push(Expansion::new(key, target, type_name));
+            }
+        }
+    }
+}
+
+/// A datatype `DatatypeIndex::expand` is expanding
+struct Expansion<'s, 'a> {
+    key: DatatypeKey,
+    label: String,
+    module: &'a CompiledModule,
+    /// The fields left to expand
+    fields: std::slice::Iter<'s, (String, &'a SignatureToken)>,
+    /// The field being expanded
+    field: Option<FieldExpansion>,
+    /// The trees of the fields already expanded
+    children: Vec<TypeTree>,
+}
+
+impl<'s, 'a> Expansion<'s, 'a> {
+    fn new(key: DatatypeKey, datatype: &'s Datatype<'a>, label: String) -> Self {
+        Expansion {
+            key,
+            label,
+            module: datatype.module,
+            fields: datatype.fields.iter(),
+            field: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// A field `DatatypeIndex::expand` is expanding
+struct FieldExpansion {
+    label: String,
+    /// The datatypes mentioned by the field's type that are left to expand
+    mentioned: std::vec::IntoIter<(DatatypeKey, String)>,
+    /// The trees of the datatypes already expanded
+    children: Vec<TypeTree>,
+}
+
+pub fn datatype_key(module: &CompiledModule, handle: DatatypeHandleIndex) -> (DatatypeKey, String) {
+    let handle = module.datatype_handle_at(handle);
+    let module_handle = module.module_handle_at(handle.module);
+    let name = module.identifier_at(handle.name).to_string();
+    let key = (
+        *module.address_identifier_at(module_handle.address),
+        module.identifier_at(module_handle.name).to_string(),
+        name.clone(),
+    );
+    (key, name)
+}
+
+/// The distinct datatypes mentioned anywhere in `token`, in order of first appearance
+fn mentioned_datatypes(
+    module: &CompiledModule,
+    token: &SignatureToken,
+) -> Vec<(DatatypeKey, String)> {
+    let mut mentioned: Vec<(DatatypeKey, String)> = Vec::new();
+
+    for token in token.preorder_traversal() {
+        let handle = match token {
+            SignatureToken::Datatype(handle) => *handle,
+            SignatureToken::DatatypeInstantiation(inner) => inner.0,
+            _ => continue,
+        };
+        let (key, name) = datatype_key(module, handle);
+        if !mentioned.iter().any(|(seen, _)| *seen == key) {
+            mentioned.push((key, name));
+        }
+    }
+
+    mentioned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::empty_module;
+
+    use crate::test_support::add_struct;
+
+    /// `struct A { b: B }` and `struct B { a: A }`. The compiler rejects this, but the renderer
+    /// must still terminate on it, as it does for recursion through type arguments.
+    fn mutually_recursive_module() -> CompiledModule {
+        let mut module = empty_module();
+
+        for (name, field, target) in [("A", "b", 1), ("B", "a", 0)] {
+            let target = SignatureToken::Datatype(DatatypeHandleIndex(target));
+            add_struct(&mut module, name, vec![(field, target)]);
+        }
+
+        module
+    }
+
+    fn render(trees: &[TypeTree], depth: usize, out: &mut Vec<String>) {
+        for tree in trees {
+            out.push(format!("{}{}", "  ".repeat(depth), tree.label));
+            render(&tree.children, depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn mutually_recursive_structs_are_cut_off() {
+        let module = mutually_recursive_module();
+        let index = DatatypeIndex::new(&[&module]);
+
+        let mut lines = Vec::new();
+        render(&index.expand_module(&module), 0, &mut lines);
+
+        assert_eq!(
+            lines,
+            [
+                "struct A",
+                "  b: B",
+                "    B",
+                "      a: A",
+                "        A <...>",
+                "struct B",
+                "  a: A",
+                "    A",
+                "      b: B",
+                "        B <...>",
+            ]
+        );
+    }
+
+    #[test]
+    fn expansion_stops_at_max_depth() {
+        let module = mutually_recursive_module();
+        let index = DatatypeIndex::new(&[&module]).with_max_depth(Some(0));
+
+        let mut lines = Vec::new();
+        render(&index.expand_module(&module), 0, &mut lines);
+
+        assert_eq!(
+            lines,
+            [
+                "struct A",
+                "  b: B",
+                "    B …",
+                "struct B",
+                "  a: A",
+                "    A …",
+            ]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use move_binary_format::{
+    file_format::{Bytecode, FunctionDefinition},
+    CompiledModule,
+};
+
+/// The function of another module that `function_def` forwards to, if its body does nothing but
+/// pass its parameters, in order, to that function and return what it returns. Rendered as
+/// `module::function`, prefixed with the address when the callee is in another package.
+pub fn forwarded_call(
+    module: &CompiledModule,
+    function_def: &FunctionDefinition,
+) -> Option<String> {
+    let code = function_def.code.as_ref()?;
+    let handle = module.function_handle_at(function_def.function);
+    let param_count = module.signature_at(handle.parameters).len();
+
+    let mut instructions = code.code.iter().peekable();
+    for param in 0..param_count {
+        match instructions.next()? {
+            Bytecode::MoveLoc(local) | Bytecode::CopyLoc(local) if *local as usize == param => {}
+            _ => return None,
+        }
+        // `&mut` parameters may be passed on as `&`
+        instructions.next_if_eq(&&Bytecode::FreezeRef);
+    }
+
+    let callee = match instructions.next()? {
+        Bytecode::Call(callee) => *callee,
+        Bytecode::CallGeneric(inst) => module.function_instantiation_at(*inst).handle,
+        _ => return None,
+    };
+    if instructions.next()? != &Bytecode::Ret || instructions.next().is_some() {
+        return None;
+    }
+
+    let callee = module.function_handle_at(callee);
+    if callee.module == handle.module
+        || module.signature_at(callee.parameters).len() != param_count
+        || module.signature_at(callee.return_).len() != module.signature_at(handle.return_).len()
+    {
+        return None;
+    }
+
+    let callee_module = module.module_handle_at(callee.module);
+    let path = format!(
+        "{}::{}",
+        module.identifier_at(callee_module.name),
+        module.identifier_at(callee.name)
+    );
+    if callee_module.address == module.self_handle().address {
+        Some(path)
+    } else {
+        let address = module.address_identifier_at(callee_module.address);
+        Some(format!("0x{}::{}", address.short_str_lossless(), path))
+    }
+}
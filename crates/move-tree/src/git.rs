@@ -0,0 +1,87 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// A copy of a repository's tree at some tag, in a temporary directory that is removed on drop
+pub struct TagCheckout {
+    dir: PathBuf,
+    /// The package in the checkout that corresponds to the one the checkout was made for
+    pub package_path: PathBuf,
+}
+
+impl Drop for TagCheckout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Extract the whole repository containing `package_path` as of `tag` (so that local dependencies
+/// still resolve) with `git archive`, and locate the same package in it
+pub fn checkout_tag(package_path: &Path, tag: &str) -> Result<TagCheckout> {
+    let commit = format!("{}^{{commit}}", tag);
+    git(package_path, &["rev-parse", "--verify", "--quiet", &commit])
+        .with_context(|| format!("`{}` is not a tag or commit of the repository", tag))?;
+    let prefix = git(package_path, &["rev-parse", "--show-prefix"])?;
+
+    let dir = std::env::temp_dir().join(format!(
+        "move-tree-{}-{}",
+        tag.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "_"),
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create temporary directory {}", dir.display()))?;
+    let checkout = TagCheckout {
+        package_path: dir.join(prefix.trim_end_matches('/')),
+        dir,
+    };
+
+    let mut archive = Command::new("git")
+        .arg("-C")
+        .arg(package_path)
+        .args(["archive", "--format=tar", &commit])
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run `git archive`")?;
+    let extracted = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(&checkout.dir)
+        .stdin(archive.stdout.take().expect("piped stdout"))
+        .status()
+        .context("Failed to run `tar`")?;
+    let archived = archive.wait().context("Failed to run `git archive`")?;
+    if !archived.success() || !extracted.success() {
+        bail!("Failed to extract `{}` to {}", tag, checkout.dir.display());
+    }
+
+    if !checkout.package_path.join("Move.toml").is_file() {
+        bail!(
+            "{} was not a Move package as of `{}`",
+            package_path.display(),
+            tag
+        );
+    }
+    Ok(checkout)
+}
+
+/// Run git in `dir` and return its trimmed stdout
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,147 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// The `.gitignore` and `.ignore` files of the directories walked, read as they are reached. Like
+/// git, a deeper file takes precedence over the ones above it and a later pattern over the earlier
+/// ones, with `.ignore` patterns coming after the `.gitignore` ones of the same directory.
+#[derive(Default)]
+pub struct IgnoreFiles {
+    dirs: BTreeMap<PathBuf, Option<Patterns>>,
+}
+
+impl IgnoreFiles {
+    /// Whether `path`, found walking `root`, is excluded by the ignore files of `root` or of a
+    /// directory between them
+    pub fn is_ignored(&mut self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut dir = path.parent();
+        while let Some(current) = dir.filter(|ancestor| ancestor.starts_with(root)) {
+            let patterns = self
+                .dirs
+                .entry(current.to_path_buf())
+                .or_insert_with(|| Patterns::load(current));
+            if let Some(ignored) = patterns.as_ref().and_then(|patterns| {
+                patterns.matched(path.strip_prefix(current).unwrap_or(path), is_dir)
+            }) {
+                return ignored;
+            }
+            dir = current.parent();
+        }
+        false
+    }
+}
+
+struct Patterns {
+    globs: GlobSet,
+    /// What each glob of `globs` does, by index
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    /// `!pattern`, re-including what an earlier pattern excluded
+    negated: bool,
+    /// `pattern/`, matching directories only
+    dir_only: bool,
+}
+
+impl Patterns {
+    /// The patterns of the ignore files of `dir`, `None` when it has none
+    fn load(dir: &Path) -> Option<Patterns> {
+        let contents = [".gitignore", ".ignore"]
+            .iter()
+            .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Patterns::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Option<Patterns> {
+        let mut builder = GlobSetBuilder::new();
+        let mut rules = Vec::new();
+        for (glob, rule) in contents.lines().filter_map(parse_line) {
+            builder.add(glob);
+            rules.push(rule);
+        }
+        if rules.is_empty() {
+            return None;
+        }
+
+        Some(Patterns {
+            globs: builder.build().ok()?,
+            rules,
+        })
+    }
+
+    /// Whether the last pattern matching `path`, relative to the directory of the ignore files,
+    /// excludes it. `None` when no pattern matches.
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        self.globs
+            .matches(path)
+            .into_iter()
+            .rev()
+            .map(|index| &self.rules[index])
+            .find(|rule| is_dir || !rule.dir_only)
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// A line of an ignore file, `None` for blank lines, comments and patterns that are not valid
+fn parse_line(line: &str) -> Option<(Glob, Rule)> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(pattern) => (true, pattern),
+        // `\#` and `\!` start a pattern with a literal `#` or `!`
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+    if pattern.trim_start_matches('/').is_empty() {
+        return None;
+    }
+
+    // A pattern with a slash is relative to the directory of the ignore file, one without
+    // matches at any depth below it
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_string(),
+        None if pattern.contains('/') => pattern.to_string(),
+        None => format!("**/{}", pattern),
+    };
+
+    let glob = GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .ok()?;
+    Some((glob, Rule { negated, dir_only }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_patterns_win_and_slashes_anchor() {
+        let patterns =
+            Patterns::parse("# vendored copies\nvendor/\n/generated\n\n*.bak\n!keep.bak\n")
+                .unwrap();
+        let matched = |path: &str, is_dir| patterns.matched(Path::new(path), is_dir);
+
+        assert_eq!(matched("vendor", true), Some(true));
+        assert_eq!(matched("pkg/vendor", true), Some(true));
+        assert_eq!(matched("vendor", false), None);
+        assert_eq!(matched("generated", true), Some(true));
+        assert_eq!(matched("pkg/generated", true), None);
+        assert_eq!(matched("pkg/Move.toml.bak", false), Some(true));
+        assert_eq!(matched("pkg/keep.bak", false), Some(false));
+        assert_eq!(matched("pkg/Move.toml", false), None);
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The nodes and edges of a resolved dependency graph, which is all `deps` needs. It is cached so
+/// that a package whose manifest and lockfile did not change is not resolved again, which can
+/// mean fetching git dependencies.
+#[derive(Serialize, Deserialize)]
+pub struct Graph {
+    pub name: String,
+    /// The id of the root package
+    pub root_id: String,
+    /// Every package of the graph, the root included
+    pub packages: Vec<Node>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    /// The address the package resolves to, `None` when unpublished
+    pub address: Option<String>,
+    /// The direct dependencies, by the name the package gives them and their id
+    pub deps: Vec<(String, String)>,
+}
+
+impl Graph {
+    pub fn ids(&self) -> Vec<String> {
+        self.packages.iter().map(|node| node.id.clone()).collect()
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.packages.iter().find(|node| node.id == id)
+    }
+
+    pub fn root(&self) -> &Node {
+        self.node(&self.root_id)
+            .expect("the root package is in its graph")
+    }
+
+    /// The direct dependencies of `node`, by the name it gives them
+    pub fn deps<'graph>(
+        &'graph self,
+        node: &'graph Node,
+    ) -> impl Iterator<Item = (&'graph str, &'graph Node)> {
+        node.deps
+            .iter()
+            .filter_map(|(dep_name, dep_id)| Some((dep_name.as_str(), self.node(dep_id)?)))
+    }
+}
+
+/// Where the graph of the package at `root` is cached, keyed on its Move.toml, the manifests of
+/// the packages it reaches through local dependencies, its Move.lock and `settings` (the
+/// environment and profile it is resolved for), under its `build/` directory. `None` without a
+/// lockfile, since the graph is then not pinned, or when one of the manifests cannot be read.
+pub fn path(root: &Path, settings: &str) -> Option<PathBuf> {
+    let manifests = manifests(root)?;
+    let lockfile = fs::read(root.join("Move.lock")).ok()?;
+
+    let mut hasher = Sha256::new();
+    let parts = manifests.iter().map(Vec::as_slice);
+    for part in parts.chain([&lockfile[..], settings.as_bytes()]) {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    let key = hasher
+        .finalize()
+        .iter()
+        .take(12)
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+
+    Some(
+        root.join("build")
+            .join("move-tree")
+            .join(format!("deps-{}.json", key)),
+    )
+}
+
+/// The Move.toml of the package at `root` and of every package it reaches through local
+/// dependencies. Move.lock does not pin those, so their graph is resolved again from their
+/// manifests, which are part of the cache key.
+fn manifests(root: &Path) -> Option<Vec<Vec<u8>>> {
+    let mut manifests = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(package) = stack.pop() {
+        let package = package.canonicalize().ok()?;
+        if !seen.insert(package.clone()) {
+            continue;
+        }
+        let manifest = fs::read(package.join("Move.toml")).ok()?;
+        let local = local_dependencies(std::str::from_utf8(&manifest).ok()?)?;
+        stack.extend(local.iter().map(|path| package.join(path)));
+        manifests.push(manifest);
+    }
+    Some(manifests)
+}
+
+/// The `local` paths of the dependencies of `manifest`, in `[dependencies]`, `[dev-dependencies]`
+/// and the `[dep-replacements]` of every environment
+fn local_dependencies(manifest: &str) -> Option<Vec<String>> {
+    let manifest = toml::from_str::<toml::Value>(manifest).ok()?;
+    let replacements = manifest
+        .get("dep-replacements")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flat_map(|environments| environments.values());
+    let tables = ["dependencies", "dev-dependencies"]
+        .into_iter()
+        .filter_map(|key| manifest.get(key))
+        .chain(replacements);
+
+    Some(
+        tables
+            .filter_map(toml::Value::as_table)
+            .flat_map(|deps| deps.values())
+            .filter_map(|dep| dep.get("local")?.as_str())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// The cached graph at `path`, or `None` when there is none or it cannot be read, such as one
+/// written by another version
+pub fn read(path: &Path) -> Option<Graph> {
+    serde_json::from_slice::<Graph>(&fs::read(path).ok()?)
+        .ok()
+        .filter(|graph| graph.node(&graph.root_id).is_some())
+}
+
+pub fn write(path: &Path, graph: &Graph) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(path, serde_json::to_vec(graph)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_dependency_manifests_are_part_of_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write(
+            "app/Move.toml",
+            "[package]\nname = \"app\"\n\n[dependencies]\nlib = { local = \"../lib\" }\n",
+        );
+        write("app/Move.lock", "");
+        write("lib/Move.toml", "[package]\nname = \"lib\"\n");
+        let root = dir.path().join("app");
+        let before = path(&root, "testnet/Release").unwrap();
+
+        write(
+            "lib/Move.toml",
+            "[package]\nname = \"lib\"\n\n[dependencies]\nutil = { local = \"../util\" }\n",
+        );
+        assert_eq!(path(&root, "testnet/Release"), None);
+
+        write("util/Move.toml", "[package]\nname = \"util\"\n");
+        let after = path(&root, "testnet/Release").unwrap();
+        assert_ne!(before, after);
+        assert_eq!(path(&root, "testnet/Release"), Some(after));
+    }
+}
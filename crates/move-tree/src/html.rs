@@ -0,0 +1,276 @@
+/// A node of a tree rendered to HTML. Labels are styled the way the terminal output is, and the
+/// styles are translated to CSS classes.
+pub struct Node {
+    pub label: String,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(label: impl Into<String>, children: Vec<Node>) -> Self {
+        Node {
+            label: label.into(),
+            children,
+        }
+    }
+
+    pub fn leaf(label: impl Into<String>) -> Self {
+        Node::new(label, Vec::new())
+    }
+}
+
+const STYLE: &str = "\
+body { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 14px; \
+margin: 1.5em; background: #fdfdfd; color: #1f2328; }
+summary { cursor: pointer; }
+.root + .root { margin-top: 1em; }
+nav { margin-bottom: 1em; }
+a { color: #0969da; }
+.children { margin-left: 0.4em; padding-left: 1em; border-left: 1px solid #d0d7de; }
+.leaf { padding-left: 1.1em; }
+.bold { font-weight: bold; }
+.dimmed { opacity: 0.6; }
+.italic { font-style: italic; }
+.underline { text-decoration: underline; }
+.reversed { background: #1f2328; color: #fdfdfd; }
+.red { color: #cf222e; }
+.green { color: #1a7f37; }
+.yellow { color: #9a6700; }
+.blue { color: #0969da; }
+.magenta { color: #8250df; }
+.cyan { color: #1b7c83; }
+";
+
+/// A self-contained HTML page showing `roots` as nested `<details>` elements, expanded by default
+pub fn document(title: &str, roots: &[Node]) -> String {
+    page(title, "", roots)
+}
+
+/// `document` for one page of a site, linking back to the site's `index.html`
+pub fn package_page(title: &str, roots: &[Node]) -> String {
+    page(
+        title,
+        "<nav><a href=\"index.html\">&larr; All packages</a></nav>\n",
+        roots,
+    )
+}
+
+/// A page of the site `--output-dir` writes
+pub struct IndexEntry {
+    pub name: String,
+    /// The environment the package was compiled for
+    pub environment: String,
+    /// Where the package was found, relative to the scanned directory
+    pub relative_path: Option<String>,
+    /// The package's page, relative to the index
+    pub file_name: String,
+}
+
+/// The `index.html` of a site, linking to the page of each package
+pub fn index(title: &str, entries: &[IndexEntry]) -> String {
+    let mut body = format!("<h1>{}</h1>\n<ul>\n", escape(title));
+    for entry in entries {
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            escape(&entry.file_name),
+            escape(&entry.name)
+        ));
+        body.push_str(&format!(
+            " <span class=\"dimmed\">[{}]</span>",
+            escape(&entry.environment)
+        ));
+        if let Some(relative) = &entry.relative_path {
+            body.push_str(&format!(
+                " <span class=\"dimmed\">({})</span>",
+                escape(relative)
+            ));
+        }
+        body.push_str("</li>\n");
+    }
+    body.push_str("</ul>\n");
+    page(title, &body, &[])
+}
+
+/// A page with `header`, raw HTML, above the trees of `roots`
+fn page(title: &str, header: &str, roots: &[Node]) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(title)));
+    html.push_str(&format!("<style>\n{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str(header);
+    for root in roots {
+        html.push_str("<div class=\"root\">\n");
+        write_node(&mut html, root);
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Write `node` and the nodes below it with an explicit stack, as a dependency tree can be deeper
+/// than the native one allows
+fn write_node(html: &mut String, node: &Node) {
+    // The nodes left to write at each level, each level but the first being the children of a
+    // node whose element is still open
+    let mut stack = vec![std::slice::from_ref(node).iter()];
+    while let Some(level) = stack.last_mut() {
+        let Some(node) = level.next() else {
+            stack.pop();
+            if !stack.is_empty() {
+                html.push_str("</div>\n</details>\n");
+            }
+            continue;
+        };
+
+        let label = ansi_to_html(&node.label);
+        if node.children.is_empty() {
+            html.push_str(&format!("<div class=\"leaf\">{}</div>\n", label));
+            continue;
+        }
+
+        html.push_str(&format!(
+            "<details open>\n<summary>{}</summary>\n<div class=\"children\">\n",
+            label
+        ));
+        stack.push(node.children.iter());
+    }
+}
+
+/// The styles set by the SGR escapes seen so far
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+    reversed: bool,
+    color: Option<&'static str>,
+}
+
+impl Style {
+    fn apply(&mut self, code: u8) {
+        match code {
+            0 => *self = Style::default(),
+            1 => self.bold = true,
+            2 => self.dimmed = true,
+            3 => self.italic = true,
+            4 => self.underline = true,
+            7 => self.reversed = true,
+            22 => {
+                self.bold = false;
+                self.dimmed = false;
+            }
+            23 => self.italic = false,
+            24 => self.underline = false,
+            27 => self.reversed = false,
+            30..=37 | 90..=97 => self.color = color_class(code % 10),
+            39 => self.color = None,
+            _ => {}
+        }
+    }
+
+    fn classes(self) -> Vec<&'static str> {
+        let mut classes = Vec::new();
+        if self.bold {
+            classes.push("bold");
+        }
+        if self.dimmed {
+            classes.push("dimmed");
+        }
+        if self.italic {
+            classes.push("italic");
+        }
+        if self.underline {
+            classes.push("underline");
+        }
+        if self.reversed {
+            classes.push("reversed");
+        }
+        classes.extend(self.color);
+        classes
+    }
+}
+
+fn color_class(color: u8) -> Option<&'static str> {
+    match color {
+        1 => Some("red"),
+        2 => Some("green"),
+        3 => Some("yellow"),
+        4 => Some("blue"),
+        5 => Some("magenta"),
+        6 => Some("cyan"),
+        _ => None,
+    }
+}
+
+/// Escape `text` and turn its SGR escapes into `<span>`s with the matching classes
+fn ansi_to_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut style = Style::default();
+    let mut open = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' || chars.peek() != Some(&'[') {
+            push_escaped(&mut html, c);
+            continue;
+        }
+        chars.next();
+        let mut sequence = String::new();
+        let mut terminator = None;
+        for c in chars.by_ref() {
+            if ('\x40'..='\x7e').contains(&c) {
+                terminator = Some(c);
+                break;
+            }
+            sequence.push(c);
+        }
+        if terminator != Some('m') {
+            continue;
+        }
+
+        let previous = style;
+        if sequence.is_empty() {
+            style.apply(0);
+        }
+        for code in sequence.split(';') {
+            if let Ok(code) = code.parse() {
+                style.apply(code);
+            }
+        }
+        if style == previous {
+            continue;
+        }
+
+        if open {
+            html.push_str("</span>");
+        }
+        let classes = style.classes();
+        open = !classes.is_empty();
+        if open {
+            html.push_str(&format!("<span class=\"{}\">", classes.join(" ")));
+        }
+    }
+
+    if open {
+        html.push_str("</span>");
+    }
+    html
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        push_escaped(&mut escaped, c);
+    }
+    escaped
+}
+
+fn push_escaped(html: &mut String, c: char) {
+    match c {
+        '&' => html.push_str("&amp;"),
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '"' => html.push_str("&quot;"),
+        c => html.push(c),
+    }
+}
@@ -0,0 +1,6640 @@
+mod abi;
+mod archive;
+mod build_output;
+mod calls;
+mod chain;
+mod compat;
+mod config;
+mod depth;
+mod diff;
+mod expand;
+mod forward;
+mod git;
+mod gitignore;
+mod graph_cache;
+mod html;
+mod lints;
+mod module_graph;
+mod named_addresses;
+mod palette;
+mod phantom;
+mod plugin;
+mod proto;
+mod resolved;
+mod reverse_deps;
+mod sarif;
+mod sexpr;
+mod sizes;
+mod split;
+mod struct_graph;
+#[cfg(test)]
+mod test_support;
+mod truncate;
+mod visibility;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use colored::{ColoredString, Colorize};
+use move_binary_format::file_format::{
+    Ability, AbilitySet, CompiledModule, DatatypeHandle, DatatypeHandleIndex, EnumDefinitionIndex,
+    FieldDefinition, FunctionDefinition, FunctionDefinitionIndex, SignatureToken,
+    StructDefinitionIndex, TableIndex, TypeParameterIndex, Visibility,
+};
+use move_bytecode_source_map::source_map::SourceName;
+use move_compiler::diagnostics::report_diagnostics_to_buffer;
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+use move_package_alt::{
+    flavor::vanilla, graph::NamedAddress, package::RootPackage, schema::Environment,
+};
+use move_package_alt_compilation::{
+    build_config::BuildConfig, build_plan::BuildPlan, compiled_package::CompiledPackage,
+};
+use regex::Regex;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use sui_package_alt::SuiFlavor;
+use walkdir::{DirEntry, WalkDir};
+
+#[derive(Parser, Debug)]
+#[command(
+    about = "Render a tree of Move modules or a dependency graph",
+    args_override_self = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Use the flags of profile NAME, a `[profiles.NAME]` table of the closest `.move-tree.toml`.
+    /// Flags given on the command line override those of the profile
+    #[arg(long, global = true, value_name = "NAME")]
+    config_profile: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render the modules and functions of each package. A single `path` may also be a `.mv`
+    /// file, or a directory of them, rendered as one package
+    Tree(TreeArgs),
+    /// Render the dependency graph of each package
+    Deps(DepsArgs),
+    /// Compare the functions of two versions of a package, given as two paths: the old version
+    /// first, then the new one
+    Diff(DiffArgs),
+    /// Render which packages of each dependency graph depend on a given package
+    ReverseDeps(ReverseDepsArgs),
+    /// Print a short summary of each package instead of its full tree
+    Info(InfoArgs),
+}
+
+impl Command {
+    fn common(&self) -> &CommonArgs {
+        match self {
+            Command::Tree(args) => &args.common,
+            Command::Deps(args) => &args.common,
+            Command::Diff(args) => &args.common,
+            Command::ReverseDeps(args) => &args.common,
+            Command::Info(args) => &args.common,
+        }
+    }
+
+    fn common_mut(&mut self) -> &mut CommonArgs {
+        match self {
+            Command::Tree(args) => &mut args.common,
+            Command::Deps(args) => &mut args.common,
+            Command::Diff(args) => &mut args.common,
+            Command::ReverseDeps(args) => &mut args.common,
+            Command::Info(args) => &mut args.common,
+        }
+    }
+
+    fn type_names(&self) -> TypeNameArgs {
+        match self {
+            Command::Tree(args) => args.type_names.clone(),
+            Command::Diff(args) => args.type_names.clone(),
+            Command::Deps(_) | Command::ReverseDeps(_) | Command::Info(_) => {
+                TypeNameArgs::default()
+            }
+        }
+    }
+
+    fn highlight(&self) -> Option<&Regex> {
+        match self {
+            Command::Tree(args) => args.highlight.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+// Options shared by every subcommand. Doc comments on flattened structs would override the
+// subcommand's own description, hence plain comments here
+#[derive(Args, Debug)]
+struct CommonArgs {
+    /// Paths to Move package directories (or folders containing Move packages). A `.tar.gz`,
+    /// `.tgz` or `.zip` archive is extracted to a temporary directory first
+    #[arg(required = true)]
+    path: Vec<PathBuf>,
+    /// Disable ANSI colors
+    #[arg(long)]
+    no_color: bool,
+    /// Colors the tree is drawn with. `deuteranopia` keeps to blues and yellows, `monochrome`
+    /// only uses bold, italic and underlining
+    #[arg(long, value_enum, default_value_t = PaletteName::Default)]
+    palette: PaletteName,
+    #[command(flatten)]
+    build: BuildArgs,
+    /// Characters used to draw the tree (defaults to unicode when the locale uses UTF-8)
+    #[arg(long, value_enum)]
+    charset: Option<Charset>,
+    /// Width of each tree level, including the branch characters (at least 2, 4 by default)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(2..))]
+    indent: Option<u8>,
+    /// Display package paths relative to PATH instead of the `path` argument they were found
+    /// under. Packages outside PATH are shown with their absolute path
+    #[arg(long, value_name = "PATH")]
+    strip_prefix: Option<PathBuf>,
+    /// Skip the directories and manifests ignored by the `.gitignore` and `.ignore` files under
+    /// PATH while looking for packages, such as vendored or generated copies of a package
+    #[arg(long)]
+    respect_gitignore: bool,
+    /// Only process the first N packages found, in path order, noting on stderr how many were
+    /// left out. A safety valve for scans of large trees, or a way to sample one
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_packages: Option<u64>,
+    /// Indent JSON output instead of printing each document on a single line (`ndjson` events
+    /// stay one per line)
+    #[arg(long)]
+    json_pretty: bool,
+    /// Only list the packages that would be compiled and the environments each would be tried
+    /// with, in order, without compiling or resolving anything
+    #[arg(long)]
+    dry_run: bool,
+    /// Only print the directory of each package found, one per line, or with `=json` a JSON
+    /// array of `{path, name}` objects
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "paths",
+        conflicts_with = "dry_run"
+    )]
+    list_packages: Option<ListFormat>,
+    /// Only print a summary of each package's Move.toml (name, version, edition and declared
+    /// dependencies) as aligned columns, or with `=json` as a JSON array
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "text",
+        conflicts_with_all = ["dry_run", "list_packages"]
+    )]
+    manifests: Option<ManifestFormat>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ListFormat {
+    /// One path per line
+    Paths,
+    /// A JSON array of `{path, name}` objects, indented with `--json-pretty`
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum ManifestFormat {
+    /// One line per package, in aligned columns
+    Text,
+    /// A JSON array of objects, indented with `--json-pretty`
+    Json,
+}
+
+// How packages are compiled and their dependencies resolved
+#[derive(Args, Clone, Debug)]
+struct BuildArgs {
+    /// Only use environment NAME from the package's Move.toml, instead of trying each one in turn.
+    /// Repeat it to try several in the order given, or with `--output`, `--split-by-package` or
+    /// `--output-dir` to write the output of each one
+    #[arg(long, value_name = "NAME")]
+    env: Vec<String>,
+    /// Build profile used when compiling packages and resolving their dependencies
+    #[arg(long, value_enum, default_value_t = Profile::Release)]
+    profile: Profile,
+}
+
+#[derive(Args, Debug)]
+struct TreeArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    /// Always treat `path` as an on-chain package id and fetch its modules over RPC. Without it,
+    /// a single bare package id such as `0x2` is looked up on chain unless a file or directory of
+    /// that name exists
+    #[arg(long)]
+    from_chain: bool,
+    /// Treat each `path` as a build output directory (such as `build/`) and render every
+    /// compiled package in it, root packages and their dependencies, without any sources
+    #[arg(long, conflicts_with_all = [
+        "from_chain", "find_type", "function", "public_struct_graph", "count_by_visibility",
+        "phantom_report", "group_functions_by_return_type", "group_by_ability",
+        "entry_signature_compat", "count_instructions", "cluster_by_package", "plugin",
+        "show_warnings", "strict", "bench",
+    ])]
+    from_build: bool,
+    /// Fullnode RPC endpoint used for on-chain lookups
+    #[arg(long, default_value = chain::DEFAULT_RPC_URL)]
+    rpc_url: String,
+    #[command(flatten)]
+    select: SelectArgs,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Tree)]
+    format: OutputFormat,
+    /// Embed each module's serialized bytecode in `--format json` (and `yaml`) output, as base64,
+    /// so the document can be read back without the package. Raw bytes with `--format proto`
+    #[arg(long)]
+    json_include_bytecode: bool,
+    /// Write `--format json`, `yaml` or `toml` output to FILE instead of stdout. When the packages
+    /// are compiled for several environments (with several `--env`, or when the fallback settles on
+    /// different ones), each environment gets its own file, named after it before the extension:
+    /// `--output api.json` writes `api.mainnet.json`, `api.testnet.json`, ... The paths written are
+    /// printed
+    #[arg(long, value_name = "FILE", conflicts_with_all = [
+        "from_chain", "from_build", "find_type", "function", "split_by_package",
+    ])]
+    output: Option<PathBuf>,
+    /// Print every struct or enum named NAME across the scanned packages instead of the tree
+    #[arg(long, value_name = "NAME")]
+    find_type: Option<String>,
+    #[command(flatten)]
+    detail: DetailArgs,
+    #[command(flatten)]
+    signature: SignatureArgs,
+    /// Show the modules and functions whose name matches REGEX in bold reverse video, keeping
+    /// the rest of the tree around them (unlike `--filter`)
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    highlight: Option<Regex>,
+    /// Make module names OSC-8 hyperlinks to their source files, which terminals that support
+    /// them open on click and others ignore (`--format tree` and `stream-tree`)
+    #[arg(long)]
+    hyperlinks: bool,
+    /// Name type parameters as the source declares them (`CoinType` rather than `T0`), in
+    /// functions and datatypes alike, wherever the package's source maps have them
+    #[arg(long)]
+    type_param_names: bool,
+    #[command(flatten)]
+    type_names: TypeNameArgs,
+    /// Print the full signature of a single function, given as MODULE::NAME, instead of the tree
+    #[arg(long, value_name = "MODULE::NAME")]
+    function: Option<String>,
+    /// Render how the package's structs and enums reference each other through their fields
+    /// (`--format tree` or `--format dot`)
+    #[arg(long)]
+    public_struct_graph: bool,
+    /// With `--format dot`, draw the modules of every package scanned and the modules they
+    /// import instead of the tree, in a cluster per package, to show both package boundaries
+    /// and how modules are coupled
+    #[arg(long, conflicts_with = "public_struct_graph")]
+    cluster_by_package: bool,
+    /// Show the serialized size of each package's modules in its header, and a grand total
+    #[arg(long)]
+    size: bool,
+    /// Print the compiler warnings of each package after its tree
+    #[arg(long)]
+    show_warnings: bool,
+    /// Print what the lint rules (those reported by `--format sarif`) find in each package after
+    /// its tree
+    #[arg(long)]
+    strict: bool,
+    /// Instead of the tree, count the functions of each module by visibility (and how many are
+    /// entry functions), with a total per package
+    #[arg(long)]
+    count_by_visibility: bool,
+    /// Instead of the tree, classify the type parameters of every generic struct and enum as
+    /// phantom or not, flagging parameters whose declaration does not match how fields use them
+    #[arg(long)]
+    phantom_report: bool,
+    /// Instead of the tree, group the functions of each package by what they return, to find
+    /// the functions producing a given type. Functions returning several values are grouped by
+    /// the whole tuple
+    #[arg(long)]
+    group_functions_by_return_type: bool,
+    /// Instead of the tree, group the structs and enums of each package by their set of
+    /// abilities, to see at once which types are objects, storable or freely copyable
+    #[arg(long)]
+    group_by_ability: bool,
+    /// Instead of the tree, check the package's entry functions against the expectation file
+    /// PATH (JSON, as `{"entry_functions": [{"module", "name", "type_params", "params",
+    /// "returns"}]}` with types named as in the tree), listing those missing or changed and
+    /// exiting with an error if any is
+    #[arg(long, value_name = "PATH")]
+    entry_signature_compat: Option<PathBuf>,
+    /// Instead of the tree, count the bytecode instructions of every function, whatever its
+    /// visibility, with a total per module and per package: a rough measure of how much logic
+    /// a package holds
+    #[arg(long)]
+    count_instructions: bool,
+    /// Cut tree lines longer than N characters, ending them with `…`. `auto` uses the width of
+    /// the terminal, and leaves lines alone when stdout is not a terminal
+    #[arg(long, value_name = "N|auto")]
+    max_line_width: Option<truncate::LineWidth>,
+    /// Fit the tree to the terminal stdout is attached to: cut lines at its width (unless
+    /// `--max-line-width` says otherwise), narrow the default indent when it is under 120
+    /// columns, and leave out parameters and return types under 50. Nothing changes when stdout
+    /// is not a terminal
+    #[arg(long)]
+    render_width_aware: bool,
+    /// Instead of the tree, run the executable PATH once per package, with the package's
+    /// `--format json` document on stdin and its directory in `MOVE_TREE_PACKAGE_PATH`
+    #[arg(long, value_name = "PATH")]
+    plugin: Option<PathBuf>,
+    /// Exit with an error when no package has a function (or, with `--structs`, a struct or enum)
+    /// left to show under the current filters
+    #[arg(long, conflicts_with_all = [
+        "find_type", "function", "public_struct_graph", "count_by_visibility", "phantom_report",
+        "group_functions_by_return_type", "group_by_ability", "entry_signature_compat",
+        "count_instructions", "cluster_by_package",
+    ])]
+    fail_if_empty: bool,
+    /// Instead of the tree, time compiling each package against reading it back from its
+    /// `build/` directory, for reporting how much `--from-build` saves
+    #[arg(long, hide = true)]
+    bench: bool,
+    /// Write the output of each package to its own file in DIR instead of stdout (`--format tree`,
+    /// `json`, `yaml` and `toml`), named after the package and the environment it was compiled
+    /// for, such as `pool.mainnet.json`. With several `--env`, each package gets a file per
+    /// environment. The paths written are printed
+    #[arg(long, value_name = "DIR", conflicts_with_all = [
+        "from_chain", "from_build", "show_warnings", "strict", "warn_large_functions",
+    ])]
+    split_by_package: Option<PathBuf>,
+    /// With `--format html`, write a site to DIR: a page per package and an `index.html` linking
+    /// to them, instead of a single page on stdout. Pages are named like `--split-by-package`
+    /// files, such as `pool.mainnet.html`
+    #[arg(long, value_name = "DIR", conflicts_with = "split_by_package")]
+    output_dir: Option<PathBuf>,
+}
+
+// Which modules and functions are collected from a package
+#[derive(Args, Debug)]
+struct SelectArgs {
+    /// Which functions to include
+    #[arg(long, value_enum, default_value_t = VisibilityFilter::Public)]
+    visibility: VisibilityFilter,
+    /// Only show modules whose name matches REGEX, plus functions whose name matches it in other
+    /// modules
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    filter: Option<Regex>,
+    /// Hide modules and functions whose name matches REGEX (applied after `--filter`)
+    #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+    exclude: Option<Regex>,
+    /// Hide modules that have nothing to show under the current filters
+    #[arg(long)]
+    no_empty_modules: bool,
+    /// Also list each module's `init` function, which is private, first in the module regardless
+    /// of `--visibility`
+    #[arg(long)]
+    show_init: bool,
+    /// Only show borrow functions, i.e. those returning a reference (`&T` or `&mut T`), which
+    /// make up a module's accessor API
+    #[arg(long)]
+    borrows_only: bool,
+    /// Only show functions taking a `signer` (`signer`, `&signer`), to audit which ones act with
+    /// an account's authority
+    #[arg(long)]
+    signer_only: bool,
+}
+
+// How types are named in signatures and fields
+#[derive(Args, Clone, Debug, Default)]
+struct TypeNameArgs {
+    /// Prefix types from other packages with their address (`0x2::coin::Coin`), so that modules
+    /// of the same name in different packages can be told apart
+    #[arg(long)]
+    qualify_external: bool,
+    /// Name types with their module (`module::Type`) even in the module that defines them, for
+    /// signatures that can be pasted elsewhere
+    #[arg(long)]
+    always_qualify: bool,
+    /// Name types defined at ADDRESS (e.g. `0x2`) by their name alone, without module or
+    /// address, to declutter signatures dominated by framework types. May be repeated
+    #[arg(long, value_name = "ADDRESS")]
+    exclude_address: Vec<AccountAddress>,
+    /// Also hide the functions whose signature mentions types, all of them defined at an
+    /// `--exclude-address`
+    #[arg(long, requires = "exclude_address")]
+    hide_excluded_functions: bool,
+}
+
+impl TypeNameArgs {
+    fn excludes(&self, module: &CompiledModule, handle: DatatypeHandleIndex) -> bool {
+        let handle = module.datatype_handle_at(handle);
+        let address = module.address_identifier_at(module.module_handle_at(handle.module).address);
+        self.exclude_address.contains(address)
+    }
+
+    /// With `--hide-excluded-functions`, whether the signature of `function_def` only uses
+    /// excluded types
+    fn hides(&self, module: &CompiledModule, function_def: &FunctionDefinition) -> bool {
+        if !self.hide_excluded_functions {
+            return false;
+        }
+        let handle = module.function_handle_at(function_def.function);
+        let mut datatypes = module
+            .signature_at(handle.parameters)
+            .0
+            .iter()
+            .chain(&module.signature_at(handle.return_).0)
+            .flat_map(SignatureToken::preorder_traversal)
+            .filter_map(|token| match token {
+                SignatureToken::Datatype(handle) => Some(*handle),
+                SignatureToken::DatatypeInstantiation(inner) => Some(inner.0),
+                _ => None,
+            })
+            .peekable();
+        datatypes.peek().is_some() && datatypes.all(|handle| self.excludes(module, handle))
+    }
+}
+
+/// The `TypeNameArgs` of the command line, read by `format_datatype`. Set once in `main`, like
+/// the color override, rather than passed through every function that formats a type
+static TYPE_NAMES: std::sync::OnceLock<TypeNameArgs> = std::sync::OnceLock::new();
+
+/// The `--highlight` pattern, read by `highlight`. Set once in `main`, like `TYPE_NAMES`
+static HIGHLIGHT: std::sync::OnceLock<Regex> = std::sync::OnceLock::new();
+
+// What is shown for each module besides its functions
+#[derive(Args, Debug, Default)]
+struct DetailArgs {
+    /// Also list each module's structs and enums, with their fields
+    #[arg(long)]
+    structs: bool,
+    /// List only the objects each module defines, i.e. its structs with `key`, with their fields
+    /// and abilities. Functions and modules without objects are left out
+    #[arg(long)]
+    only_objects: bool,
+    /// Also list each module's structs and enums, with the fields of every type defined in the
+    /// package expanded recursively
+    #[arg(long)]
+    expand_types: bool,
+    /// Expand types at most N levels deep under `--expand-types`, ending deeper ones with `…`
+    #[arg(long, value_name = "N", requires = "expand_types")]
+    max_depth_types: Option<usize>,
+    /// Mark functions whose body only passes their parameters on to a function of another
+    /// module, with `-> forwards to module::function`, to see through facade modules
+    #[arg(long)]
+    flatten_reexports: bool,
+    /// Number the fields listed by `--structs` from 0 in declaration order, which is the order
+    /// BCS serializes them in
+    #[arg(long, requires = "structs")]
+    show_field_offsets: bool,
+    /// Number the variants of enums listed by `--structs` from 0 in declaration order, as
+    /// `[0] Pending`: the tag BCS serializes an enum value with
+    #[arg(long, requires = "structs")]
+    show_enum_tags: bool,
+    /// Estimate how many bytes BCS serializes each struct and enum listed by `--structs` to,
+    /// from its field types: `40 bytes` when they all have a fixed width, `~41 bytes + variable`
+    /// with vectors, strings, type parameters or types of other packages
+    #[arg(long, requires = "structs")]
+    struct_sizes: bool,
+    /// Count how many functions of the package call each function, with `[N callers]`.
+    /// Non-entry functions nothing calls are highlighted
+    #[arg(long)]
+    call_counts: bool,
+    /// Mark functions whose body has more than N bytecode instructions with `[N instructions]`,
+    /// and list them after each package's tree, as candidates for splitting or gas review
+    #[arg(long, value_name = "N")]
+    warn_large_functions: Option<usize>,
+}
+
+// Which parts of function signatures the tree shows
+#[derive(Args, Clone, Copy, Debug, Default)]
+struct SignatureArgs {
+    /// Leave out the type parameters and parameters of functions
+    #[arg(long)]
+    no_params: bool,
+    /// Leave out the return types of functions
+    #[arg(long)]
+    no_returns: bool,
+    /// Highlight `signer` parameters (`signer`, `&signer`), which carry the authority of the
+    /// account calling the function, unlike Sui's `TxContext`
+    #[arg(long)]
+    show_signer_params: bool,
+}
+
+#[derive(Args, Debug)]
+struct DepsArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    /// Instead of the tree, list every package of the graph once with its id and resolved address
+    #[arg(long)]
+    resolve_only: bool,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = DepsFormat::Tree)]
+    format: DepsFormat,
+    /// Leave out dev-dependencies, which are only part of the graph with `--profile dev`
+    #[arg(long)]
+    no_dev: bool,
+    /// How the dependencies of each package are ordered
+    #[arg(long, value_enum, default_value_t = SortDeps::Name)]
+    sort_deps: SortDeps,
+    /// Instead of the tree, print the longest chain of dependencies from the package, and its
+    /// length
+    #[arg(long, conflicts_with = "resolve_only")]
+    depth_report: bool,
+    /// Instead of the tree, print every named address the package's code can use with the
+    /// address it resolves to in the selected environment
+    #[arg(long, conflicts_with_all = ["resolve_only", "depth_report"])]
+    resolve_named_addresses: bool,
+    /// Print chains of packages that each have a single dependency on one line, as `a > b > c`,
+    /// down to the first package with several (in the tree and `--format html`)
+    #[arg(long, conflicts_with_all = ["resolve_only", "depth_report", "resolve_named_addresses"])]
+    collapse_single_child: bool,
+    /// Instead of the tree, print a SHA-256 of the resolved graph: the id, address and
+    /// Move.lock source (git revision included) of every package, and the edges between them.
+    /// Checkouts resolving to the same dependencies get the same digest
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "resolve_only", "depth_report", "resolve_named_addresses", "collapse_single_child",
+        ]
+    )]
+    lockfile_digest: bool,
+    /// Only draw the subtree of the dependency NAME (a package name or id) found anywhere in the
+    /// graph, as the root of the tree: what that dependency brings in
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = [
+            "resolve_only", "depth_report", "resolve_named_addresses", "lockfile_digest",
+        ]
+    )]
+    follow_only: Option<String>,
+    /// Instead of the tree, check that no package whose name matches REGEX (such as
+    /// `^internal_|_private$`, or `a|b` for a list) is among the transitive dependencies of the
+    /// others, listing each one reached and through which dependencies, and fail if any is.
+    /// Packages matching REGEX themselves are not checked
+    #[arg(
+        long,
+        value_name = "REGEX",
+        value_parser = Regex::new,
+        conflicts_with_all = [
+            "resolve_only", "depth_report", "resolve_named_addresses", "lockfile_digest",
+            "follow_only", "collapse_single_child",
+        ]
+    )]
+    check_no_private_deps: Option<Regex>,
+    /// Resolve the dependency graph again instead of reading it from the cache kept under
+    /// `build/`, which is only reused while Move.toml (and those of local dependencies), Move.lock,
+    /// `--env` and `--profile` stay the same. A package without a Move.lock is never cached, as
+    /// nothing pins its graph.
+    #[arg(long)]
+    no_cache: bool,
+}
+
+#[derive(Args, Debug)]
+struct DiffArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    #[command(flatten)]
+    select: SelectArgs,
+    #[command(flatten)]
+    type_names: TypeNameArgs,
+    /// Only report functions present in both versions whose signature changed
+    #[arg(long)]
+    only_changed_sigs: bool,
+    /// Compare a single local package with the package deployed at PACKAGE_ID instead, datatypes
+    /// included, and fail if they differ. The on-chain version is the old side of the diff
+    #[arg(long, value_name = "PACKAGE_ID")]
+    verify_onchain: Option<String>,
+    /// Fullnode RPC endpoint used by `--verify-onchain`
+    #[arg(long, default_value = chain::DEFAULT_RPC_URL)]
+    rpc_url: String,
+    /// Compare a single local package with the same package as of git tag TAG (or any commit)
+    /// instead. The tag's tree is extracted to a temporary directory, removed afterwards
+    #[arg(long, value_name = "TAG", conflicts_with = "verify_onchain")]
+    since_tag: Option<String>,
+    /// Instead of the diff, list the functions whose visibility changed, whatever
+    /// `--visibility` says, with the old and new visibility. Narrowing changes, such as `public`
+    /// to `public(package)`, are marked as breaking
+    #[arg(long, conflicts_with = "only_changed_sigs")]
+    compare_visibility: bool,
+    /// Number the type parameters of each function in the order its signature first uses them
+    /// before comparing, so that reordering type parameters alone is not reported as a change
+    #[arg(long)]
+    normalize_generics: bool,
+}
+
+#[derive(Args, Debug)]
+struct ReverseDepsArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    /// The dependency to start from, by package name or id
+    #[arg(long, value_name = "NAME")]
+    package: String,
+}
+
+#[derive(Args, Debug)]
+struct InfoArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+    /// Print the summary as JSON (an array when several packages are found)
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable tree
+    Tree,
+    /// The same tree, written module by module as each is collected rather than once the whole
+    /// package is, so that large packages show output right away
+    StreamTree,
+    /// One JSON event per line, emitted while packages are being processed
+    Ndjson,
+    /// SARIF 2.1.0 report of lint findings, e.g. for GitHub code scanning
+    Sarif,
+    /// Graphviz DOT (only for `--public-struct-graph`)
+    Dot,
+    /// A JSON object per package (an array of them when several packages are found)
+    Json,
+    /// The same structure as `json`, as YAML
+    Yaml,
+    /// A self-contained HTML page with the tree, where modules can be collapsed
+    Html,
+    /// The same structure as `json`, as a binary `move_tree.Packages` protobuf message (see
+    /// `proto/move_tree.proto`)
+    Proto,
+    /// A JSON ABI of each package for SDK code generators: the functions `--visibility` selects
+    /// and every struct and enum, with types as structured values tagged by kind (`u64`,
+    /// `vector`, `object`, `type_param`, ...) and the ability constraints of type parameters
+    Abi,
+    /// The same structure as `json`, as TOML, with modules and functions as arrays of tables.
+    /// Several packages are listed under `[[packages]]`
+    Toml,
+    /// The same structure as `json`, as s-expressions for Emacs and other Lisps to `read`: a
+    /// `(package "name" (module "name" (fun "name" (params ...) ...)))` form per package
+    Sexpr,
+}
+
+impl OutputFormat {
+    fn data_format(self, json_pretty: bool) -> Option<DataFormat> {
+        match self {
+            OutputFormat::Json => Some(DataFormat::Json {
+                pretty: json_pretty,
+            }),
+            OutputFormat::Yaml => Some(DataFormat::Yaml),
+            OutputFormat::Toml => Some(DataFormat::Toml),
+            OutputFormat::Tree
+            | OutputFormat::StreamTree
+            | OutputFormat::Ndjson
+            | OutputFormat::Sarif
+            | OutputFormat::Dot
+            | OutputFormat::Html
+            | OutputFormat::Proto
+            | OutputFormat::Abi
+            | OutputFormat::Sexpr => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DepsFormat {
+    /// Human-readable tree (or list, for `--resolve-only`)
+    Tree,
+    /// A JSON object per package (an array of them when several packages are found)
+    Json,
+    /// The same structure as `json`, as YAML
+    Yaml,
+    /// A self-contained HTML page with the tree, where dependency subtrees can be collapsed
+    Html,
+}
+
+impl DepsFormat {
+    fn data_format(self, json_pretty: bool) -> Option<DataFormat> {
+        match self {
+            DepsFormat::Tree | DepsFormat::Html => None,
+            DepsFormat::Json => Some(DataFormat::Json {
+                pretty: json_pretty,
+            }),
+            DepsFormat::Yaml => Some(DataFormat::Yaml),
+        }
+    }
+}
+
+/// The formats that serialize the same structure as text
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DataFormat {
+    Json { pretty: bool },
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    fn print(self, value: &impl Serialize) -> Result<()> {
+        println!("{}", self.render(value)?);
+        Ok(())
+    }
+
+    fn render(self, value: &impl Serialize) -> Result<String> {
+        Ok(match self {
+            DataFormat::Json { pretty: true } => serde_json::to_string_pretty(value)?,
+            DataFormat::Json { pretty: false } => serde_json::to_string(value)?,
+            DataFormat::Yaml => serde_yaml::to_string(value)?.trim_end().to_string(),
+            DataFormat::Toml => toml::to_string(value)?.trim_end().to_string(),
+        })
+    }
+
+    /// The extension of files holding a document in this format
+    fn extension(self) -> &'static str {
+        match self {
+            DataFormat::Json { .. } => "json",
+            DataFormat::Yaml => "yaml",
+            DataFormat::Toml => "toml",
+        }
+    }
+
+    /// Print `values` as [`DataFormat::render_all`] renders them
+    fn print_all<T: Serialize>(self, values: &[T]) -> Result<()> {
+        println!("{}", self.render_all(values)?);
+        Ok(())
+    }
+
+    /// Render a single value as is, and several as an array. A TOML document cannot be an array,
+    /// so there they are the `packages` array of a table instead.
+    fn render_all<T: Serialize>(self, values: &[T]) -> Result<String> {
+        #[derive(Serialize)]
+        struct Packages<'a, T> {
+            packages: &'a [T],
+        }
+
+        match values {
+            [value] => self.render(value),
+            values if self == DataFormat::Toml => self.render(&Packages { packages: values }),
+            values => self.render(&values),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Profile {
+    /// Sets `BuildConfig::test_mode`, so `#[test_only]` code, the `tests/` directory and
+    /// dev-dependencies are included
+    Dev,
+    /// `BuildConfig::default()`: only the code that is published
+    Release,
+}
+
+impl Profile {
+    fn build_config(self) -> BuildConfig {
+        match self {
+            Profile::Dev => BuildConfig {
+                test_mode: true,
+                ..BuildConfig::default()
+            },
+            Profile::Release => BuildConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PaletteName {
+    /// Green functions, yellow parameters, magenta return types and cyan modules
+    Default,
+    /// Distinguishable with red-green color blindness
+    Deuteranopia,
+    /// No colors, for terminals or readers where hues do not help
+    Monochrome,
+}
+
+impl PaletteName {
+    fn palette(self) -> &'static palette::Palette {
+        match self {
+            PaletteName::Default => &palette::DEFAULT,
+            PaletteName::Deuteranopia => &palette::DEUTERANOPIA,
+            PaletteName::Monochrome => &palette::MONOCHROME,
+        }
+    }
+}
+
+/// The `--palette` of the command line, read through `palette()`. Set once in `main`, like
+/// `TYPE_NAMES`
+static PALETTE: std::sync::OnceLock<&'static palette::Palette> = std::sync::OnceLock::new();
+
+fn palette() -> &'static palette::Palette {
+    PALETTE.get().copied().unwrap_or(&palette::DEFAULT)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Charset {
+    /// `|--`, `` `-- `` and `|`
+    Ascii,
+    /// `├──`, `└──` and `│`
+    Unicode,
+}
+
+impl Charset {
+    /// Unicode if the locale (`LC_ALL`, then `LC_CTYPE`, then `LANG`) asks for UTF-8
+    fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if locale.contains("utf-8") || locale.contains("utf8") {
+            Charset::Unicode
+        } else {
+            Charset::Ascii
+        }
+    }
+}
+
+/// The strings every tree renderer draws its branches with
+struct Branches {
+    branch: String,
+    last: String,
+    pipe: String,
+    space: String,
+}
+
+impl Branches {
+    /// Branches `indent` columns wide: a glyph, a horizontal run, then one space
+    fn new(charset: Charset, indent: usize) -> Self {
+        let (tee, corner, horizontal, vertical) = match charset {
+            Charset::Ascii => ('|', '`', "-", '|'),
+            Charset::Unicode => ('├', '└', "─", '│'),
+        };
+        let run = horizontal.repeat(indent.saturating_sub(2));
+
+        Branches {
+            branch: format!("{}{} ", tee, run),
+            last: format!("{}{} ", corner, run),
+            pipe: format!("{}{}", vertical, " ".repeat(indent.saturating_sub(1))),
+            space: " ".repeat(indent),
+        }
+    }
+
+    fn from_args(args: &CommonArgs) -> Self {
+        Branches::new(
+            args.charset.unwrap_or_else(Charset::detect),
+            args.indent.unwrap_or(DEFAULT_INDENT) as usize,
+        )
+    }
+
+    /// The connector in front of a node
+    fn branch(&self, is_last: bool) -> &str {
+        if is_last {
+            &self.last
+        } else {
+            &self.branch
+        }
+    }
+
+    /// The prefix continuing below a node, in front of its children
+    fn indent(&self, is_last: bool) -> &str {
+        if is_last {
+            &self.space
+        } else {
+            &self.pipe
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum VisibilityFilter {
+    /// Only `public` functions
+    Public,
+    /// Only `public(package)` / `public(friend)` functions
+    Friend,
+    /// Only private functions
+    Private,
+    /// Every function regardless of visibility
+    All,
+}
+
+impl VisibilityFilter {
+    fn includes(self, visibility: Visibility) -> bool {
+        match self {
+            VisibilityFilter::Public => visibility == Visibility::Public,
+            VisibilityFilter::Friend => visibility == Visibility::Friend,
+            VisibilityFilter::Private => visibility == Visibility::Private,
+            VisibilityFilter::All => true,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ModuleInfo {
+    name: String,
+    /// Modules declared as friends, i.e. the modules (besides this one) that may call its
+    /// `public(package)` functions
+    friends: Vec<String>,
+    functions: Vec<FunctionInfo>,
+    /// Structs and enums, only collected for `--structs`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    structs: Vec<StructInfo>,
+    /// Expanded datatypes, only collected for `--expand-types`
+    #[serde(skip)]
+    datatypes: Vec<expand::TypeTree>,
+    /// The serialized module, only collected for `--json-include-bytecode`
+    #[serde(
+        serialize_with = "serialize_base64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    bytecode: Option<Vec<u8>>,
+    /// The absolute path of the module's source file, only set for `--hyperlinks`
+    #[serde(skip)]
+    source_path: Option<PathBuf>,
+}
+
+/// A struct or enum, as listed by `--structs`
+#[derive(PartialEq, Serialize)]
+struct StructInfo {
+    /// `struct` or `enum`
+    kind: &'static str,
+    name: String,
+    #[serde(serialize_with = "serialize_abilities")]
+    abilities: AbilitySet,
+    type_params: Vec<String>,
+    /// The fields of a struct (empty for enums)
+    #[serde(flatten)]
+    fields: Fields,
+    /// The variants of an enum, in declaration order (empty for structs)
+    variants: Vec<VariantInfo>,
+    /// Whether the struct looks like the module's one-time witness
+    #[serde(rename = "one_time_witness")]
+    is_otw: bool,
+    /// The estimated serialized size, with `--struct-sizes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<sizes::Size>,
+}
+
+#[derive(PartialEq, Serialize)]
+struct VariantInfo {
+    name: String,
+    #[serde(flatten)]
+    fields: Fields,
+    /// The position of the variant in its enum, with `--show-enum-tags`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<usize>,
+}
+
+/// Fields in declaration order. Positional fields (`struct Wrapper(u64)`) are named by their
+/// index.
+#[derive(PartialEq, Serialize)]
+struct Fields {
+    positional: bool,
+    fields: Vec<FieldInfo>,
+}
+
+#[derive(PartialEq, Serialize)]
+struct FieldInfo {
+    name: String,
+    #[serde(rename = "type")]
+    type_: String,
+    /// The position of the field in its struct or variant, with `--show-field-offsets`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<usize>,
+}
+
+/// A struct or enum definition found by `--find-type`
+struct TypeDefinition {
+    package: String,
+    module: String,
+    name: String,
+    kind: &'static str,
+    abilities: AbilitySet,
+}
+
+#[derive(Serialize)]
+struct FunctionInfo {
+    name: String,
+    #[serde(serialize_with = "serialize_visibility")]
+    visibility: Visibility,
+    #[serde(rename = "entry")]
+    is_entry: bool,
+    /// Whether this is the module initializer Sui runs once at publish
+    #[serde(rename = "init")]
+    is_init: bool,
+    type_params: Vec<String>,
+    params: Vec<String>,
+    returns: Vec<String>,
+    /// The function this one only forwards to, with `--flatten-reexports`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forwards_to: Option<String>,
+    /// How many functions of the package call this one, with `--call-counts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    callers: Option<usize>,
+    /// The number of instructions of the function's body, for those over
+    /// `--warn-large-functions`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<usize>,
+    /// Whether the function returns a reference, which ties the caller to what it borrowed from
+    #[serde(rename = "borrow")]
+    returns_reference: bool,
+    /// The positions of the parameters that are a `signer` or a reference to one
+    #[serde(skip)]
+    signer_params: Vec<usize>,
+}
+
+/// Run `move-tree` with the arguments of the process
+pub async fn run() -> Result<()> {
+    let args = std::env::args_os().collect::<Vec<_>>();
+    let mut cli = Cli::parse_from(&args);
+    if let Some(profile) = &cli.config_profile {
+        cli = Cli::parse_from(config::with_profile(args, profile)?);
+    }
+
+    if cli.command.common().no_color {
+        colored::control::set_override(false);
+    }
+    PALETTE
+        .set(cli.command.common().palette.palette())
+        .expect("the palette is only set once");
+    TYPE_NAMES
+        .set(cli.command.type_names())
+        .expect("type names are only set once");
+    if let Some(highlight) = cli.command.highlight() {
+        HIGHLIGHT
+            .set(highlight.clone())
+            .expect("the highlight pattern is only set once");
+    }
+
+    // Held until `run` returns, however it returns, so that the extracted archives are removed
+    let _extracted = archive::extract_all(&mut cli.command.common_mut().path)?;
+
+    if cli.command.common().dry_run {
+        return print_build_plan(cli.command.common());
+    }
+    if let Some(format) = cli.command.common().list_packages {
+        return list_packages(cli.command.common(), format);
+    }
+    if let Some(format) = cli.command.common().manifests {
+        return print_manifests(cli.command.common(), format);
+    }
+
+    if let Command::Tree(args) = &mut cli.command {
+        if args.render_width_aware {
+            fit_to_terminal(args);
+        }
+    }
+
+    match &cli.command {
+        Command::Tree(args) => render_trees(args).await,
+        Command::Deps(args) => render_dependency_graphs(args).await,
+        Command::Diff(args) => diff_packages(args).await,
+        Command::ReverseDeps(args) => reverse_deps::render(args).await,
+        Command::Info(args) => summarize_packages(args).await,
+    }
+}
+
+/// The plan printed by `--dry-run`: each package found with the environments it would be
+/// compiled for, the first one that works being used
+fn print_build_plan(args: &CommonArgs) -> Result<()> {
+    let package_roots = discover_packages(args)?;
+
+    let mut rows = Vec::new();
+    for root in &package_roots {
+        let (envs, synthetic) = environments_to_try(root, &args.build.env)?;
+        let mut names = envs.iter().map(|env| env.name().to_string());
+        let mut plan = names.next().unwrap_or_default().bold().to_string();
+        let fallbacks = names.collect::<Vec<_>>();
+        if !fallbacks.is_empty() {
+            plan.push_str(
+                &format!(" (then {})", fallbacks.join(", "))
+                    .dimmed()
+                    .to_string(),
+            );
+        }
+        if synthetic {
+            plan.push_str(&" (no environments declared)".dimmed().to_string());
+        }
+        rows.push((root.display().to_string(), plan));
+    }
+
+    let width = rows.iter().map(|(path, _)| path.len()).max().unwrap_or(0);
+    for (path, plan) in rows {
+        println!("{:width$}  {}", path, plan);
+    }
+    Ok(())
+}
+
+/// A package in `--list-packages=json` output
+#[derive(Serialize)]
+struct ListedPackage {
+    path: String,
+    /// The `name` of the package's Move.toml
+    name: Option<String>,
+}
+
+fn list_packages(args: &CommonArgs, format: ListFormat) -> Result<()> {
+    let package_roots = discover_packages(args)?;
+    match format {
+        ListFormat::Paths => {
+            for root in &package_roots {
+                println!("{}", root.display());
+            }
+            Ok(())
+        }
+        ListFormat::Json => {
+            let packages = package_roots
+                .iter()
+                .map(|root| {
+                    Ok(ListedPackage {
+                        path: root.display().to_string(),
+                        name: manifest_package_field(root, "name")?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            print_json(&packages, args.json_pretty)
+        }
+    }
+}
+
+/// What `--manifests` shows of a package's Move.toml
+#[derive(Serialize)]
+struct ManifestSummary {
+    path: String,
+    name: Option<String>,
+    version: Option<String>,
+    edition: Option<String>,
+    /// The names of the `[dependencies]`, sorted
+    dependencies: Vec<String>,
+}
+
+fn print_manifests(args: &CommonArgs, format: ManifestFormat) -> Result<()> {
+    let summaries = discover_packages(args)?
+        .iter()
+        .map(|root| {
+            let manifest = read_manifest(root)?;
+            let package = manifest.get("package");
+            let field = |key: &str| {
+                package
+                    .and_then(|package| package.get(key))
+                    .and_then(|value| value.as_str())
+                    .map(str::to_string)
+            };
+            let mut dependencies = manifest
+                .get("dependencies")
+                .and_then(toml::Value::as_table)
+                .map(|deps| deps.keys().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            dependencies.sort();
+            Ok(ManifestSummary {
+                path: root.display().to_string(),
+                name: field("name"),
+                version: field("version"),
+                edition: field("edition"),
+                dependencies,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if format == ManifestFormat::Json {
+        return print_json(&summaries, args.json_pretty);
+    }
+
+    let missing = || "-".to_string();
+    let rows = summaries
+        .iter()
+        .map(|summary| {
+            [
+                summary.path.clone(),
+                summary.name.clone().unwrap_or_else(missing),
+                summary.version.clone().unwrap_or_else(missing),
+                summary.edition.clone().unwrap_or_else(missing),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let widths = (0..4)
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    for (row, summary) in rows.iter().zip(&summaries) {
+        let dependencies = if summary.dependencies.is_empty() {
+            "(no dependencies)".dimmed().to_string()
+        } else {
+            summary.dependencies.join(", ")
+        };
+        println!(
+            "{}  {}  {}  {}  {}",
+            format!("{:width$}", row[0], width = widths[0]),
+            format!("{:width$}", row[1], width = widths[1]).bold(),
+            format!("{:width$}", row[2], width = widths[2]).dimmed(),
+            format!("{:width$}", row[3], width = widths[3]).dimmed(),
+            dependencies
+        );
+    }
+    Ok(())
+}
+
+/// The packages found under the `path` arguments, or an error if there are none
+fn discover_packages(args: &CommonArgs) -> Result<Vec<PathBuf>> {
+    let mut package_roots = find_all_packages(&args.path, args.respect_gitignore)?;
+    if package_roots.is_empty() {
+        bail!("No Move.toml found under {}", display_paths(&args.path));
+    }
+    if let Some(max) = args.max_packages {
+        let max = usize::try_from(max).unwrap_or(usize::MAX);
+        if package_roots.len() > max {
+            eprintln!(
+                "{} only processing the first {} of {} packages found (`--max-packages`)",
+                palette().note.paint("note:").bold(),
+                max,
+                package_roots.len()
+            );
+            package_roots.truncate(max);
+        }
+    }
+    Ok(package_roots)
+}
+
+/// The packages to render, each with the build settings to compile it with. With several `--env`
+/// and output written to files (`--output`, `--split-by-package` or `--output-dir`), every package
+/// is rendered once per environment, each to a file named after the environment. Otherwise each
+/// package is rendered once, for the first of the environments that works.
+fn renderings(args: &TreeArgs, package_roots: &[PathBuf]) -> Vec<(PathBuf, BuildArgs)> {
+    let build = &args.common.build;
+    let per_environment = build.env.len() > 1
+        && (args.output.is_some() || args.split_by_package.is_some() || args.output_dir.is_some());
+
+    let mut renderings = Vec::new();
+    for root in package_roots {
+        if per_environment {
+            for env in &build.env {
+                let build = BuildArgs {
+                    env: vec![env.clone()],
+                    ..build.clone()
+                };
+                renderings.push((root.clone(), build));
+            }
+        } else {
+            renderings.push((root.clone(), build.clone()));
+        }
+    }
+    renderings
+}
+
+async fn render_trees(args: &TreeArgs) -> Result<()> {
+    if args.public_struct_graph && !matches!(args.format, OutputFormat::Tree | OutputFormat::Dot) {
+        bail!("`--public-struct-graph` only supports `--format tree` and `--format dot`");
+    }
+    if args.cluster_by_package && args.format != OutputFormat::Dot {
+        bail!("`--cluster-by-package` only supports `--format dot`");
+    }
+    if args.format == OutputFormat::Dot && !args.public_struct_graph && !args.cluster_by_package {
+        bail!("`--format dot` requires `--public-struct-graph` or `--cluster-by-package`");
+    }
+    if args.count_by_visibility && args.format != OutputFormat::Tree {
+        bail!("`--count-by-visibility` only supports `--format tree`");
+    }
+    if args.phantom_report && args.format != OutputFormat::Tree {
+        bail!("`--phantom-report` only supports `--format tree`");
+    }
+    if args.group_functions_by_return_type && args.format != OutputFormat::Tree {
+        bail!("`--group-functions-by-return-type` only supports `--format tree`");
+    }
+    if args.group_by_ability && args.format != OutputFormat::Tree {
+        bail!("`--group-by-ability` only supports `--format tree`");
+    }
+    if args.entry_signature_compat.is_some() && args.format != OutputFormat::Tree {
+        bail!("`--entry-signature-compat` only supports `--format tree`");
+    }
+    if args.count_instructions && args.format != OutputFormat::Tree {
+        bail!("`--count-instructions` only supports `--format tree`");
+    }
+    if args.plugin.is_some() && args.format != OutputFormat::Tree {
+        bail!("`--plugin` only supports `--format tree`; the plugin decides what to print");
+    }
+    if args.strict && !matches!(args.format, OutputFormat::Tree | OutputFormat::StreamTree) {
+        bail!(
+            "`--strict` only supports `--format tree` and `stream-tree`; `--format sarif` \
+             reports the same lints"
+        );
+    }
+    if args.hyperlinks && !matches!(args.format, OutputFormat::Tree | OutputFormat::StreamTree) {
+        bail!("`--hyperlinks` only supports `--format tree` and `stream-tree`");
+    }
+    if args.json_include_bytecode
+        && !matches!(
+            args.format,
+            OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Toml | OutputFormat::Proto
+        )
+    {
+        bail!(
+            "`--json-include-bytecode` only supports `--format json`, `yaml`, `toml` and `proto`"
+        );
+    }
+    if args.split_by_package.is_some()
+        && !matches!(
+            args.format,
+            OutputFormat::Tree | OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Toml
+        )
+    {
+        bail!("`--split-by-package` only supports `--format tree`, `json`, `yaml` and `toml`");
+    }
+    if args.split_by_package.is_some() {
+        colored::control::set_override(false);
+    }
+    if args.output_dir.is_some() && args.format != OutputFormat::Html {
+        bail!("`--output-dir` only supports `--format html`");
+    }
+    if args.output.is_some() && args.format.data_format(false).is_none() {
+        bail!("`--output` only supports `--format json`, `--format yaml` and `--format toml`");
+    }
+    if args.fail_if_empty && args.format == OutputFormat::Sarif {
+        bail!("`--fail-if-empty` does not support `--format sarif`");
+    }
+
+    if args.from_build {
+        return render_build_outputs(args);
+    }
+
+    if let Some(package_id) = chain_package_id(args)? {
+        if args.split_by_package.is_some() {
+            bail!("`--split-by-package` needs package directories, not an on-chain package");
+        }
+        return render_chain_package(args, &package_id).await;
+    }
+
+    if let [path] = args.common.path.as_slice() {
+        if build_output::is_module_set(path) {
+            if args.split_by_package.is_some() {
+                bail!("`--split-by-package` needs package directories, not `.mv` files");
+            }
+            return render_module_files(args, path);
+        }
+    }
+
+    let package_roots = discover_packages(&args.common)?;
+    let build = &args.common.build;
+
+    if args.bench {
+        return bench_from_build(args, &package_roots).await;
+    }
+
+    if let Some(type_name) = &args.find_type {
+        return find_type(&package_roots, type_name, build).await;
+    }
+
+    if let Some(selector) = &args.function {
+        return describe_function(args, &package_roots, selector).await;
+    }
+
+    if args.public_struct_graph {
+        return render_struct_graphs(args, &package_roots).await;
+    }
+
+    if args.cluster_by_package {
+        return render_module_graph(args, &package_roots).await;
+    }
+
+    if args.count_by_visibility {
+        return count_by_visibility(args, &package_roots).await;
+    }
+
+    if args.phantom_report {
+        return report_phantom_params(args, &package_roots).await;
+    }
+
+    if args.group_functions_by_return_type {
+        return group_functions_by_return_type(args, &package_roots).await;
+    }
+
+    if args.group_by_ability {
+        return group_by_ability(args, &package_roots).await;
+    }
+
+    if let Some(expectations) = &args.entry_signature_compat {
+        return check_entry_signatures(args, &package_roots, expectations).await;
+    }
+
+    if args.count_instructions {
+        return count_instructions(args, &package_roots).await;
+    }
+
+    if let Some(plugin) = &args.plugin {
+        return run_plugin(args, &package_roots, plugin).await;
+    }
+
+    match args.format {
+        OutputFormat::Ndjson => return emit_package_events(args, &package_roots).await,
+        OutputFormat::Json
+        | OutputFormat::Yaml
+        | OutputFormat::Toml
+        | OutputFormat::Proto
+        | OutputFormat::Sexpr => {
+            return print_packages_data(args, &package_roots).await;
+        }
+        OutputFormat::Sarif => return emit_sarif_report(&package_roots, build).await,
+        OutputFormat::Html => return print_packages_html(args, &package_roots).await,
+        OutputFormat::Abi => return print_abis(args, &package_roots).await,
+        OutputFormat::Tree | OutputFormat::StreamTree | OutputFormat::Dot => {}
+    }
+
+    let branches = Branches::from_args(&args.common);
+    let mut out = truncate::Truncate::new(io::stdout(), max_line_width(args));
+    let mut split = match &args.split_by_package {
+        Some(dir) => Some(split::SplitOutput::new(dir, "txt")?),
+        None => None,
+    };
+    let package_count = package_roots.len();
+    let mut total_size = 0;
+    let mut shown = false;
+    let mut first = true;
+    for (root, build) in renderings(args, &package_roots) {
+        if !first && split.is_none() {
+            println!();
+        }
+        first = false;
+
+        let CompileOutput {
+            compiled,
+            environment,
+            warnings,
+        } = compile_package_output(&root, &build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let package_name = compiled
+            .compiled_package_info
+            .package_name
+            .as_str()
+            .to_string();
+        let size = if args.size {
+            let size = serialized_size(root_modules(&compiled))?;
+            total_size += size;
+            Some(size)
+        } else {
+            None
+        };
+        let relative_path = package_path_label(&args.common, &root);
+        let sources = if args.hyperlinks {
+            module_sources(&compiled)
+        } else {
+            BTreeMap::new()
+        };
+        let with_source = |module| annotate_module(args, &compiled, &sources, module);
+        let mut large = Vec::new();
+        let mut buffer = Vec::new();
+        let mut target: &mut dyn Write = if split.is_some() {
+            &mut buffer
+        } else {
+            &mut out
+        };
+        if args.format == OutputFormat::StreamTree {
+            let compiled_modules = sorted_by_name(root_modules(&compiled));
+            let selector = ModuleSelector::new(&args.select, &args.detail, &compiled_modules);
+            shown |= stream_package_tree(
+                &mut target,
+                &branches,
+                relative_path.as_deref(),
+                &package_name,
+                size,
+                compiled_modules
+                    .iter()
+                    .filter_map(|compiled| selector.select(compiled))
+                    .map(with_source)
+                    .inspect(|module| large.extend(large_functions([module]))),
+                args.signature,
+            )?;
+        } else {
+            let modules = write_compiled_package_tree(
+                &mut target,
+                args,
+                &branches,
+                &root,
+                &compiled,
+                &sources,
+                size,
+            )?;
+            shown |= modules.iter().any(|module| !is_empty_module(module));
+            large = large_functions(&modules);
+        }
+        if let Some(split) = &mut split {
+            split.write(&package_name, &environment, &buffer)?;
+        }
+        if let Some(threshold) = args.detail.warn_large_functions {
+            print_large_functions(&branches, threshold, &large);
+        }
+        if args.show_warnings {
+            print_warnings(&branches, &warnings);
+        }
+        if args.strict {
+            print_findings(&branches, &lints::check_compiled_package(&root, &compiled));
+        }
+    }
+
+    if args.size && package_count > 1 {
+        println!();
+        println!(
+            "{} {} across {} packages",
+            palette().header.paint("total"),
+            format_size(total_size).bold(),
+            package_count
+        );
+    }
+
+    check_not_empty(args, shown)
+}
+
+/// `module` with its source file from `sources` and, under `--type-param-names`, its type
+/// parameters named after the source
+fn annotate_module(
+    args: &TreeArgs,
+    compiled: &CompiledPackage,
+    sources: &BTreeMap<String, PathBuf>,
+    mut module: ModuleInfo,
+) -> ModuleInfo {
+    module.source_path = sources.get(&module.name).cloned();
+    if args.type_param_names {
+        name_type_params(&mut module, compiled);
+    }
+    module
+}
+
+/// Write the tree of `compiled`, the package at `root`, the way `tree --format tree` prints it,
+/// with the modules linked to their file in `sources`, returning the modules it shows
+fn write_compiled_package_tree(
+    out: &mut impl Write,
+    args: &TreeArgs,
+    branches: &Branches,
+    root: &Path,
+    compiled: &CompiledPackage,
+    sources: &BTreeMap<String, PathBuf>,
+    size: Option<usize>,
+) -> Result<Vec<ModuleInfo>> {
+    let modules = select_modules(&args.select, &args.detail, root_modules(compiled))
+        .into_iter()
+        .map(|module| annotate_module(args, compiled, sources, module))
+        .collect::<Vec<_>>();
+    write_package_tree(
+        out,
+        branches,
+        package_path_label(&args.common, root).as_deref(),
+        compiled.compiled_package_info.package_name.as_str(),
+        size,
+        &modules,
+        args.signature,
+    )?;
+    Ok(modules)
+}
+
+/// Compile the package at `path` and render its tree the way `move-tree tree PATH --no-color
+/// --charset ascii` prints it, such as for asserting against a snapshot
+pub async fn render_package_tree_to_string(path: &Path) -> Result<String> {
+    let cli = Cli::try_parse_from(
+        ["move-tree", "tree", "--charset", "ascii"]
+            .map(std::ffi::OsStr::new)
+            .into_iter()
+            .chain([path.as_os_str()]),
+    )?;
+    let Command::Tree(args) = cli.command else {
+        unreachable!("parsed as `move-tree tree`");
+    };
+
+    let compiled = compile_package(path, &args.common.build).await?;
+    let mut out = Vec::new();
+    write_compiled_package_tree(
+        &mut out,
+        &args,
+        &Branches::from_args(&args.common),
+        path,
+        &compiled,
+        &BTreeMap::new(),
+        None,
+    )?;
+    Ok(without_colors(&String::from_utf8(out)?))
+}
+
+/// `text` without the SGR escapes `colored` styles it with, whether or not it colors stdout
+fn without_colors(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+async fn bench_from_build(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let branches = Branches::from_args(&args.common);
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let start = Instant::now();
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let compile_time = start.elapsed();
+
+        let mut line = format!(
+            "{} {}",
+            palette().header.paint("bench"),
+            compiled.compiled_package_info.package_name.as_str().bold()
+        );
+        if let Some(relative) = package_path_label(&args.common, root) {
+            line.push_str(&format!(" {}", format!("({})", relative).dimmed()));
+        }
+        println!("{}", line);
+        println!(
+            "{}{:<11}{}",
+            branches.branch(false),
+            "compile",
+            format_duration(compile_time)
+        );
+
+        let build_dir = root.join("build");
+        if !build_dir.is_dir() {
+            println!(
+                "{}{}",
+                branches.last,
+                format!("(no {} to load from)", build_dir.display()).dimmed()
+            );
+            continue;
+        }
+        let start = Instant::now();
+        build_output::read_packages(&[build_dir])?;
+        let load_time = start.elapsed();
+        println!(
+            "{}{:<11}{}",
+            branches.branch(false),
+            "from build",
+            format_duration(load_time)
+        );
+        println!(
+            "{}{:<11}{:.1}x",
+            branches.last,
+            "speedup",
+            compile_time.as_secs_f64() / load_time.as_secs_f64().max(1e-9)
+        );
+    }
+
+    Ok(())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+/// With `--fail-if-empty`, fail unless some module had something to show
+fn check_not_empty(args: &TreeArgs, shown: bool) -> Result<()> {
+    if args.fail_if_empty && !shown {
+        bail!("No functions to show under the current filters");
+    }
+    Ok(())
+}
+
+const DEFAULT_INDENT: u8 = 4;
+
+/// Below this many columns, `--render-width-aware` leaves function signatures out
+const COMPACT_WIDTH: usize = 50;
+
+/// Adjust `args` to the width of the terminal for `--render-width-aware`, keeping the options
+/// that were given explicitly
+fn fit_to_terminal(args: &mut TreeArgs) {
+    let Some(columns) = truncate::LineWidth::Auto.columns() else {
+        return;
+    };
+
+    args.max_line_width
+        .get_or_insert(truncate::LineWidth::Columns(columns));
+    args.common.indent.get_or_insert(match columns {
+        0..80 => 2,
+        80..120 => 3,
+        _ => DEFAULT_INDENT,
+    });
+    if columns < COMPACT_WIDTH {
+        args.signature.no_params = true;
+        args.signature.no_returns = true;
+    }
+}
+
+fn max_line_width(args: &TreeArgs) -> Option<usize> {
+    args.max_line_width.and_then(truncate::LineWidth::columns)
+}
+
+async fn render_dependency_graphs(args: &DepsArgs) -> Result<()> {
+    let package_roots = discover_packages(&args.common)?;
+    if args.resolve_only {
+        if args.format == DepsFormat::Html {
+            bail!("`--resolve-only` does not support `--format html`");
+        }
+        return resolved::print_packages(args, &package_roots).await;
+    }
+    if args.depth_report {
+        if args.format == DepsFormat::Html {
+            bail!("`--depth-report` does not support `--format html`");
+        }
+        return depth::print_reports(args, &package_roots).await;
+    }
+    if args.resolve_named_addresses {
+        if args.format == DepsFormat::Html {
+            bail!("`--resolve-named-addresses` does not support `--format html`");
+        }
+        return named_addresses::print(args, &package_roots).await;
+    }
+    if args.lockfile_digest {
+        if args.format == DepsFormat::Html {
+            bail!("`--lockfile-digest` does not support `--format html`");
+        }
+        return print_lockfile_digests(args, &package_roots).await;
+    }
+    if let Some(internal) = &args.check_no_private_deps {
+        if args.format != DepsFormat::Tree {
+            bail!("`--check-no-private-deps` only supports `--format tree`");
+        }
+        return check_private_deps(args, &package_roots, internal).await;
+    }
+    if args.follow_only.is_some() && args.format != DepsFormat::Tree {
+        bail!("`--follow-only` only supports `--format tree`");
+    }
+
+    if let Some(format) = args.format.data_format(args.common.json_pretty) {
+        let mut graphs = Vec::new();
+        for root in &package_roots {
+            let graph = load_graph_snapshot(root, &args.common.build, args.no_cache).await?;
+            let dev = DevDependencies::of(root, &graph, args).await?;
+            let order = DependencyOrder::new(args.sort_deps, &graph, &dev);
+            graphs.push(dependency_graph_data(
+                package_path_label(&args.common, root),
+                &graph,
+                &dev,
+                &order,
+            ));
+        }
+        return format.print_all(&graphs);
+    }
+
+    if args.format == DepsFormat::Html {
+        use_html_colors(&args.common);
+        let mut names = Vec::new();
+        let mut nodes = Vec::new();
+        for root in &package_roots {
+            let graph = load_graph_snapshot(root, &args.common.build, args.no_cache).await?;
+            let dev = DevDependencies::of(root, &graph, args).await?;
+            names.push(graph.name.clone());
+            let order = DependencyOrder::new(args.sort_deps, &graph, &dev);
+            let sources = DependencySources::of(root, &graph);
+            nodes.push(dependency_graph_node(
+                package_path_label(&args.common, root),
+                &graph,
+                &dev,
+                &order,
+                &sources,
+                args.collapse_single_child,
+            ));
+        }
+        print_html(&names, &nodes);
+        return Ok(());
+    }
+
+    let branches = Branches::from_args(&args.common);
+
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let graph = load_graph_snapshot(&root, &args.common.build, args.no_cache).await?;
+        let dev = DevDependencies::of(&root, &graph, args).await?;
+        let order = DependencyOrder::new(args.sort_deps, &graph, &dev);
+        let sources = DependencySources::of(&root, &graph);
+        print_dependency_graph(
+            &branches,
+            package_path_label(&args.common, &root),
+            &graph,
+            &dev,
+            &order,
+            &sources,
+            args.collapse_single_child,
+            args.follow_only.as_deref(),
+        );
+    }
+
+    Ok(())
+}
+
+/// The facts printed by `info`
+#[derive(Serialize)]
+struct PackageSummary {
+    name: String,
+    relative_path: Option<String>,
+    /// The `version` field of the manifest, if any
+    version: Option<String>,
+    edition: Option<String>,
+    /// The environment the package was compiled for
+    environment: String,
+    /// How many environments the package can be built for, including the defaults
+    environments: usize,
+    modules: usize,
+    public_functions: usize,
+    /// Entry functions of any visibility, i.e. what transactions can call directly
+    entry_functions: usize,
+    /// The number of entry functions of each module that has any
+    entry_functions_by_module: BTreeMap<String, usize>,
+    /// Serialized size of the package's modules, in bytes
+    bytecode_size: usize,
+    dependencies: Vec<String>,
+}
+
+async fn summarize_packages(args: &InfoArgs) -> Result<()> {
+    let package_roots = discover_packages(&args.common)?;
+
+    let mut summaries = Vec::new();
+    for root in &package_roots {
+        summaries.push(summarize_package(&args.common, root).await?);
+    }
+
+    if args.json {
+        let format = DataFormat::Json {
+            pretty: args.common.json_pretty,
+        };
+        return format.print_all(&summaries);
+    }
+
+    let mut first = true;
+    for summary in &summaries {
+        if !first {
+            println!();
+        }
+        first = false;
+        print_package_summary(summary);
+    }
+
+    Ok(())
+}
+
+async fn summarize_package(args: &CommonArgs, root: &Path) -> Result<PackageSummary> {
+    let root_package = load_dependency_graph(root, &args.build)
+        .await
+        .with_context(|| format!("Failed to load dependency graph at {}", root.display()))?;
+    let output = compile_package_output(root, &args.build)
+        .await
+        .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+    let environments = RootPackage::<SuiFlavor>::environments(root)
+        .with_context(|| format!("Failed to read environments for {}", root.display()))?
+        .len();
+
+    let package_info = root_package.package_info();
+    let mut dependencies = package_info
+        .direct_deps()
+        .iter()
+        .map(|(dep_name, dep_info)| {
+            render_dependency_label(
+                dep_name.as_str(),
+                dep_info.display_name(),
+                dep_info.id().as_str(),
+            )
+        })
+        .collect::<Vec<_>>();
+    dependencies.sort();
+
+    let compiled = &output.compiled;
+    let public_functions = root_modules(compiled)
+        .flat_map(|module| module.function_defs())
+        .filter(|def| def.visibility == Visibility::Public)
+        .count();
+    let entry_functions_by_module = root_modules(compiled)
+        .map(|module| {
+            let entry = module.function_defs().iter().filter(|def| def.is_entry);
+            (module.name().to_string(), entry.count())
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect::<BTreeMap<_, _>>();
+
+    Ok(PackageSummary {
+        name: root_package.display_name().to_string(),
+        relative_path: package_path_label(args, root),
+        version: manifest_version(root)?,
+        edition: package_info.edition().map(|edition| edition.to_string()),
+        environment: output.environment.clone(),
+        environments,
+        modules: root_modules(compiled).count(),
+        public_functions,
+        entry_functions: entry_functions_by_module.values().sum(),
+        entry_functions_by_module,
+        bytecode_size: serialized_size(root_modules(compiled))?,
+        dependencies,
+    })
+}
+
+/// The `version` of the `[package]` section of the package's Move.toml. The package system
+/// ignores this field, so it is read from the manifest directly.
+fn manifest_version(path: &Path) -> Result<Option<String>> {
+    manifest_package_field(path, "version")
+}
+
+fn manifest_package_field(path: &Path, key: &str) -> Result<Option<String>> {
+    Ok(read_manifest(path)?
+        .get("package")
+        .and_then(|package| package.get(key))
+        .and_then(|value| value.as_str())
+        .map(str::to_string))
+}
+
+fn print_package_summary(summary: &PackageSummary) {
+    let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let dependencies = if summary.dependencies.is_empty() {
+        "(none)".dimmed().to_string()
+    } else {
+        summary.dependencies.join(", ")
+    };
+    let mut entry_functions = summary.entry_functions.to_string();
+    if !summary.entry_functions_by_module.is_empty() {
+        let by_module = summary
+            .entry_functions_by_module
+            .iter()
+            .map(|(module, count)| format!("{}: {}", module, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        entry_functions.push_str(&format!(" {}", format!("({})", by_module).dimmed()));
+    }
+
+    let mut rows = vec![("name", summary.name.bold().to_string())];
+    if let Some(relative) = &summary.relative_path {
+        rows.push(("path", relative.clone()));
+    }
+    rows.extend([
+        ("version", or_dash(&summary.version)),
+        ("edition", or_dash(&summary.edition)),
+        (
+            "environment",
+            format!(
+                "{} {}",
+                summary.environment,
+                format!("({} available)", summary.environments).dimmed()
+            ),
+        ),
+        ("modules", summary.modules.to_string()),
+        ("public functions", summary.public_functions.to_string()),
+        ("entry functions", entry_functions),
+        ("bytecode size", format_size(summary.bytecode_size)),
+        ("dependencies", dependencies),
+    ]);
+
+    let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+    for (key, value) in rows {
+        println!(
+            "{}  {}",
+            palette().header.paint(&format!("{:width$}", key)),
+            value
+        );
+    }
+}
+
+/// The serialized size of `compiled_modules`, i.e. what publishing them would upload
+fn serialized_size<'a>(
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Result<usize> {
+    let mut total = 0;
+    for module in compiled_modules {
+        total += module_bytes(module)?.len();
+    }
+    Ok(total)
+}
+
+fn module_bytes(module: &CompiledModule) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    module
+        .serialize_with_version(module.version, &mut bytes)
+        .with_context(|| format!("Failed to serialize module `{}`", module.name()))?;
+    Ok(bytes)
+}
+
+/// With `--json-include-bytecode`, store the bytecode of each of `modules` from the matching
+/// compiled module
+fn attach_bytecode<'a>(
+    args: &TreeArgs,
+    modules: &mut [ModuleInfo],
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Result<()> {
+    if !args.json_include_bytecode {
+        return Ok(());
+    }
+    let compiled_modules = compiled_modules.collect::<Vec<_>>();
+    for module in modules {
+        if let Some(compiled) = compiled_modules
+            .iter()
+            .find(|compiled| compiled.name().as_str() == module.name)
+        {
+            module.bytecode = Some(module_bytes(compiled)?);
+        }
+    }
+    Ok(())
+}
+
+fn format_size(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = 1024.0 * 1024.0;
+
+    let bytes_f = bytes as f64;
+    if bytes_f >= MIB {
+        format!("{:.1} MiB", bytes_f / MIB)
+    } else if bytes_f >= KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Events emitted by `--format ndjson`, one JSON object per line. Every event carries the package
+/// path (and, once known, the package and module names) so that clients can rebuild the tree
+/// incrementally.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    PackageStarted {
+        path: &'a str,
+    },
+    Module {
+        package: &'a str,
+        path: &'a str,
+        module: &'a str,
+        friends: &'a [String],
+    },
+    Function {
+        package: &'a str,
+        path: &'a str,
+        module: &'a str,
+        #[serde(flatten)]
+        function: &'a FunctionInfo,
+    },
+    PackageFinished {
+        package: &'a str,
+        path: &'a str,
+        modules: usize,
+        functions: usize,
+    },
+    Error {
+        path: &'a str,
+        message: String,
+    },
+}
+
+async fn emit_package_events(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut failures = 0;
+    let mut shown = false;
+
+    for root in package_roots {
+        let path = root.display().to_string();
+        emit_event(&Event::PackageStarted { path: &path })?;
+
+        let compiled = match compile_package(root, &args.common.build).await {
+            Ok(compiled) => compiled,
+            Err(err) => {
+                failures += 1;
+                emit_event(&Event::Error {
+                    path: &path,
+                    message: format!("{:#}", err),
+                })?;
+                continue;
+            }
+        };
+
+        let modules = select_package_modules(args, &compiled);
+        shown |= modules.iter().any(|module| !is_empty_module(module));
+        let package = compiled.compiled_package_info.package_name.as_str();
+        emit_module_events(package, &path, &modules)?;
+    }
+
+    if failures > 0 {
+        bail!("{} of {} package(s) failed", failures, package_roots.len());
+    }
+
+    check_not_empty(args, shown)
+}
+
+/// Emit the `module` and `function` events for an already collected package, followed by its
+/// `package_finished` event
+fn emit_module_events(package: &str, path: &str, modules: &[ModuleInfo]) -> Result<()> {
+    for module in modules {
+        emit_event(&Event::Module {
+            package,
+            path,
+            module: &module.name,
+            friends: &module.friends,
+        })?;
+        for function in &module.functions {
+            emit_event(&Event::Function {
+                package,
+                path,
+                module: &module.name,
+                function,
+            })?;
+        }
+    }
+
+    emit_event(&Event::PackageFinished {
+        package,
+        path,
+        modules: modules.len(),
+        functions: modules.iter().map(|module| module.functions.len()).sum(),
+    })
+}
+
+fn emit_event(event: &Event<'_>) -> Result<()> {
+    // stdout is line buffered, so each event reaches the consumer as soon as it is printed
+    println!("{}", serde_json::to_string(event)?);
+    Ok(())
+}
+
+/// Decide whether `path` names an on-chain package rather than something on disk. A path that
+/// exists locally always wins, so `--from-chain` is needed to force a lookup in that case. Chain
+/// lookups only apply when a single path is given.
+fn chain_package_id(args: &TreeArgs) -> Result<Option<String>> {
+    let [path] = args.common.path.as_slice() else {
+        if args.from_chain {
+            bail!("`--from-chain` takes a single package id");
+        }
+        return Ok(None);
+    };
+    let arg = path.to_string_lossy();
+
+    if args.from_chain {
+        if !chain::looks_like_address(&arg) {
+            bail!("`{}` is not a valid package id", arg);
+        }
+        return Ok(Some(arg.into_owned()));
+    }
+
+    if chain::looks_like_address(&arg) && !path.exists() {
+        Ok(Some(arg.into_owned()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// A package in `--from-build --format json` (and `yaml`) output
+#[derive(Serialize)]
+struct BuiltPackageJson<'a> {
+    package_name: &'a str,
+    id: String,
+    /// The root package this one was built as a dependency of (`null` for root packages)
+    dependency_of: Option<&'a str>,
+    modules: &'a [ModuleInfo],
+}
+
+fn render_build_outputs(args: &TreeArgs) -> Result<()> {
+    if !matches!(
+        args.format,
+        OutputFormat::Tree | OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Toml
+    ) {
+        bail!("`--from-build` only supports `--format tree`, `json`, `yaml` and `toml`");
+    }
+
+    let packages = build_output::read_packages(&args.common.path)?;
+    if packages.is_empty() {
+        bail!(
+            "No compiled packages found under {}",
+            display_paths(&args.common.path)
+        );
+    }
+    let mut modules = packages
+        .iter()
+        .map(|package| select_modules(&args.select, &args.detail, package.modules.iter()))
+        .collect::<Vec<_>>();
+    for (package, modules) in packages.iter().zip(&mut modules) {
+        attach_bytecode(args, modules, package.modules.iter())?;
+    }
+    check_not_empty(
+        args,
+        modules
+            .iter()
+            .flatten()
+            .any(|module| !is_empty_module(module)),
+    )?;
+
+    if let Some(format) = args.format.data_format(args.common.json_pretty) {
+        let packages = packages
+            .iter()
+            .zip(&modules)
+            .map(|(package, modules)| BuiltPackageJson {
+                package_name: &package.name,
+                id: package.id.to_hex_literal(),
+                dependency_of: package.dependency_of.as_deref(),
+                modules,
+            })
+            .collect::<Vec<_>>();
+        return format.print_all(&packages);
+    }
+
+    let branches = Branches::from_args(&args.common);
+    let mut out = truncate::Truncate::new(io::stdout(), max_line_width(args));
+    for (index, (package, modules)) in packages.iter().zip(&modules).enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        let size = if args.size {
+            Some(serialized_size(package.modules.iter())?)
+        } else {
+            None
+        };
+        let role = package
+            .dependency_of
+            .as_ref()
+            .map(|root| format!("dependency of {}", root));
+        write_package_tree(
+            &mut out,
+            &branches,
+            role.as_deref(),
+            &package.name,
+            size,
+            modules,
+            args.signature,
+        )?;
+    }
+    Ok(())
+}
+
+async fn render_chain_package(args: &TreeArgs, package_id: &str) -> Result<()> {
+    if args.format == OutputFormat::Ndjson {
+        emit_event(&Event::PackageStarted { path: package_id })?;
+    }
+
+    let compiled_modules = match chain::fetch_package_modules(&args.rpc_url, package_id).await {
+        Ok(modules) => modules,
+        Err(err) if args.format == OutputFormat::Ndjson => {
+            emit_event(&Event::Error {
+                path: package_id,
+                message: format!("{:#}", err),
+            })?;
+            bail!("failed to fetch package {}", package_id);
+        }
+        Err(err) => return Err(err),
+    };
+
+    render_module_set(args, package_id, package_id, &compiled_modules)
+}
+
+/// Render the `.mv` file `path`, or the `.mv` files directly in directory `path`, as a package
+/// named after it
+fn render_module_files(args: &TreeArgs, path: &Path) -> Result<()> {
+    let display = path.display().to_string();
+    if args.format == OutputFormat::Ndjson {
+        emit_event(&Event::PackageStarted { path: &display })?;
+    }
+
+    let compiled_modules = match build_output::read_module_set(path) {
+        Ok(modules) => modules,
+        Err(err) if args.format == OutputFormat::Ndjson => {
+            emit_event(&Event::Error {
+                path: &display,
+                message: format!("{:#}", err),
+            })?;
+            bail!("failed to read modules from {}", display);
+        }
+        Err(err) => return Err(err),
+    };
+
+    let name = if path.is_file() {
+        path.file_stem()
+    } else {
+        path.file_name()
+    };
+    let name = name.map_or_else(
+        || display.clone(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    render_module_set(args, &name, &display, &compiled_modules)
+}
+
+/// Render modules that do not come from a compiled package, be it fetched from chain or loose
+/// `.mv` files, as the package `name`. `path` is where they come from, for `--format ndjson`.
+fn render_module_set(
+    args: &TreeArgs,
+    name: &str,
+    path: &str,
+    compiled_modules: &[CompiledModule],
+) -> Result<()> {
+    if let Some(type_name) = &args.find_type {
+        let definitions = find_type_definitions(name, compiled_modules.iter(), type_name);
+        print_type_definitions(type_name, &definitions);
+        return Ok(());
+    }
+
+    if let Some(selector) = &args.function {
+        let (module_name, function_name) = parse_function_selector(selector)?;
+        return print_function_detail(
+            &Branches::from_args(&args.common),
+            compiled_modules.iter(),
+            module_name,
+            function_name,
+        );
+    }
+
+    if args.public_struct_graph {
+        let nodes = struct_graph::build(compiled_modules.iter());
+        struct_graph::print(args, None, name, &nodes);
+        return Ok(());
+    }
+
+    if args.cluster_by_package {
+        let mut graph = module_graph::ModuleGraph::default();
+        let owners = compiled_modules
+            .iter()
+            .map(|module| {
+                let key = (*module.address(), module.name().to_string());
+                (key, name.to_string())
+            })
+            .collect();
+        graph.add(name, compiled_modules.iter(), &owners);
+        print!("{}", graph.render_dot());
+        return Ok(());
+    }
+
+    if args.count_by_visibility {
+        let modules = collect_all_visibilities(&args.select, compiled_modules.iter());
+        visibility::print_counts(&Branches::from_args(&args.common), None, name, &modules);
+        return Ok(());
+    }
+
+    if args.phantom_report {
+        let datatypes = phantom::analyze(compiled_modules.iter());
+        phantom::print_report(&Branches::from_args(&args.common), None, name, &datatypes);
+        return Ok(());
+    }
+
+    if args.group_functions_by_return_type {
+        let modules = select_modules(&args.select, &args.detail, compiled_modules.iter());
+        print_return_type_groups(args, None, name, &modules);
+        return Ok(());
+    }
+
+    if args.group_by_ability {
+        let modules = select_modules(&args.select, &ability_detail(), compiled_modules.iter());
+        print_ability_groups(&Branches::from_args(&args.common), None, name, &modules);
+        return Ok(());
+    }
+
+    if let Some(path) = &args.entry_signature_compat {
+        let expectations = compat::read(path)?;
+        let modules = collect_modules(compiled_modules.iter(), VisibilityFilter::All, false);
+        let branches = Branches::from_args(&args.common);
+        return print_entry_compat(&branches, None, name, path, &expectations, &modules);
+    }
+
+    if args.count_instructions {
+        let counts = instruction_counts(compiled_modules.iter());
+        print_instruction_counts(&Branches::from_args(&args.common), None, name, &counts);
+        return Ok(());
+    }
+
+    let modules = select_modules(&args.select, &args.detail, compiled_modules.iter());
+    check_not_empty(args, modules.iter().any(|module| !is_empty_module(module)))?;
+    if let Some(plugin) = &args.plugin {
+        let package = PackageJson {
+            package_name: name,
+            relative_path: None,
+            environment: None,
+            modules: &modules,
+        };
+        return plugin::run(plugin, None, &package);
+    }
+
+    match args.format {
+        OutputFormat::Tree | OutputFormat::StreamTree => {
+            let size = if args.size {
+                Some(serialized_size(compiled_modules.iter())?)
+            } else {
+                None
+            };
+            write_package_tree(
+                &mut truncate::Truncate::new(io::stdout(), max_line_width(args)),
+                &Branches::from_args(&args.common),
+                None,
+                name,
+                size,
+                &modules,
+                args.signature,
+            )?;
+            if let Some(threshold) = args.detail.warn_large_functions {
+                print_large_functions(
+                    &Branches::from_args(&args.common),
+                    threshold,
+                    &large_functions(&modules),
+                );
+            }
+            if args.strict {
+                print_findings(
+                    &Branches::from_args(&args.common),
+                    &lints::check_modules(name, compiled_modules.iter()),
+                );
+            }
+            Ok(())
+        }
+        OutputFormat::Dot => {
+            unreachable!("`--format dot` is rejected without a graph to draw")
+        }
+        OutputFormat::Ndjson => emit_module_events(name, path, &modules),
+        OutputFormat::Json
+        | OutputFormat::Yaml
+        | OutputFormat::Toml
+        | OutputFormat::Proto
+        | OutputFormat::Sexpr => {
+            let mut modules = modules;
+            attach_bytecode(args, &mut modules, compiled_modules.iter())?;
+            let package = PackageJson {
+                package_name: name,
+                relative_path: None,
+                environment: None,
+                modules: &modules,
+            };
+            match args.format.data_format(args.common.json_pretty) {
+                Some(format) => format.print(&package),
+                None if args.format == OutputFormat::Sexpr => sexpr::print(&[package]),
+                None => proto::print(&[package]),
+            }
+        }
+        OutputFormat::Sarif => {
+            print_sarif_report(&lints::check_modules(name, compiled_modules.iter()))
+        }
+        OutputFormat::Html => {
+            use_html_colors(&args.common);
+            let size = if args.size {
+                Some(serialized_size(compiled_modules.iter())?)
+            } else {
+                None
+            };
+            let node = package_tree_node(None, name, size, &modules, args.signature);
+            print_html(&[name.to_string()], &[node]);
+            Ok(())
+        }
+        OutputFormat::Abi => DataFormat::Json {
+            pretty: args.common.json_pretty,
+        }
+        .print(&abi::Package {
+            package_name: name.to_string(),
+            relative_path: None,
+            modules: abi::modules(compiled_modules.iter(), args.select.visibility),
+        }),
+    }
+}
+
+/// A package in `--format json` (and `yaml`) output. The fields are stable:
+///
+/// - `package_name`: the package name (the package id for on-chain packages)
+/// - `relative_path`: the package directory relative to the scanned path, with `/` separators, or
+///   `null` when the package is the scanned path itself
+/// - `environment`: the environment the package was compiled for (`null` for on-chain packages)
+/// - `modules`: the modules, each with `name`, `friends` and `functions`
+#[derive(Serialize)]
+struct PackageJson<'a> {
+    package_name: &'a str,
+    relative_path: Option<String>,
+    environment: Option<&'a str>,
+    modules: &'a [ModuleInfo],
+}
+
+async fn print_packages_data(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut outputs = Vec::new();
+    for (root, build) in renderings(args, package_roots) {
+        let output = compile_package_output(&root, &build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let mut modules = select_package_modules(args, &output.compiled);
+        attach_bytecode(args, &mut modules, root_modules(&output.compiled))?;
+        outputs.push((root, output, modules));
+    }
+
+    let packages = outputs
+        .iter()
+        .map(|(root, output, modules)| PackageJson {
+            package_name: output.compiled.compiled_package_info.package_name.as_str(),
+            relative_path: package_path_label(&args.common, root),
+            environment: Some(&output.environment),
+            modules,
+        })
+        .collect::<Vec<_>>();
+
+    check_not_empty(
+        args,
+        packages
+            .iter()
+            .flat_map(|package| package.modules)
+            .any(|module| !is_empty_module(module)),
+    )?;
+
+    if args.format == OutputFormat::Proto {
+        return proto::print(&packages);
+    }
+    if args.format == OutputFormat::Sexpr {
+        return sexpr::print(&packages);
+    }
+    let format = args
+        .format
+        .data_format(args.common.json_pretty)
+        .expect("a data format");
+    if let Some(file) = &args.output {
+        return write_data_output(format, file, &packages);
+    }
+    if let Some(dir) = &args.split_by_package {
+        let mut split = split::SplitOutput::new(dir, format.extension())?;
+        for package in &packages {
+            let contents = format!("{}\n", format.render(package)?);
+            split.write(
+                package.package_name,
+                package.environment.unwrap_or_default(),
+                contents.as_bytes(),
+            )?;
+        }
+        return Ok(());
+    }
+    format.print_all(&packages)
+}
+
+async fn print_abis(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut packages = Vec::new();
+    for root in package_roots {
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        packages.push(abi::Package {
+            package_name: compiled.compiled_package_info.package_name.to_string(),
+            relative_path: package_path_label(&args.common, root),
+            modules: abi::modules(root_modules(&compiled), args.select.visibility),
+        });
+    }
+
+    check_not_empty(
+        args,
+        packages
+            .iter()
+            .flat_map(|package| &package.modules)
+            .any(|module| !module.functions.is_empty() || !module.types.is_empty()),
+    )?;
+    DataFormat::Json {
+        pretty: args.common.json_pretty,
+    }
+    .print_all(&packages)
+}
+
+/// Write `packages` to `file`, or, when they were compiled for several environments, the packages
+/// of each environment to `file` with the environment's name before the extension, such as
+/// `api.mainnet.json` for `api.json`
+fn write_data_output(format: DataFormat, file: &Path, packages: &[PackageJson]) -> Result<()> {
+    let mut environments: Vec<(&str, Vec<&PackageJson>)> = Vec::new();
+    for package in packages {
+        let environment = package.environment.unwrap_or_default();
+        match environments
+            .iter_mut()
+            .find(|(name, _)| *name == environment)
+        {
+            Some((_, group)) => group.push(package),
+            None => environments.push((environment, vec![package])),
+        }
+    }
+
+    if environments.len() < 2 {
+        return write_output(file, &format.render_all(packages)?);
+    }
+    for (environment, group) in &environments {
+        write_output(
+            &environment_path(file, environment),
+            &format.render_all(group)?,
+        )?;
+    }
+    Ok(())
+}
+
+/// `file` with `environment` before its extension: `out.mainnet.txt` for `out.txt`, and
+/// `out.mainnet` for `out`
+fn environment_path(file: &Path, environment: &str) -> PathBuf {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match file.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, environment, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, environment),
+    };
+    file.with_file_name(name)
+}
+
+/// Write `contents` and a final newline to `path`, and print the path
+fn write_output(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, format!("{}\n", contents))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+async fn print_packages_html(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    use_html_colors(&args.common);
+
+    let mut names = Vec::new();
+    let mut environments = Vec::new();
+    let mut relative_paths = Vec::new();
+    let mut nodes = Vec::new();
+    let mut shown = false;
+    for (root, build) in renderings(args, package_roots) {
+        let CompileOutput {
+            compiled,
+            environment,
+            ..
+        } = compile_package_output(&root, &build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let modules = select_package_modules(args, &compiled);
+        shown |= modules.iter().any(|module| !is_empty_module(module));
+        let size = if args.size {
+            Some(serialized_size(root_modules(&compiled))?)
+        } else {
+            None
+        };
+        let name = compiled.compiled_package_info.package_name.to_string();
+        let relative_path = package_path_label(&args.common, &root);
+        nodes.push(package_tree_node(
+            relative_path.as_deref(),
+            &name,
+            size,
+            &modules,
+            args.signature,
+        ));
+        names.push(name);
+        environments.push(environment);
+        relative_paths.push(relative_path);
+    }
+
+    check_not_empty(args, shown)?;
+    if let Some(dir) = &args.output_dir {
+        return write_html_site(dir, names, environments, relative_paths, nodes);
+    }
+    print_html(&names, &nodes);
+    Ok(())
+}
+
+/// Write a page per package to `dir`, linked from an `index.html` listing them all. Links are
+/// relative, so the site works from the filesystem as well as served.
+fn write_html_site(
+    dir: &Path,
+    names: Vec<String>,
+    environments: Vec<String>,
+    relative_paths: Vec<Option<String>>,
+    nodes: Vec<html::Node>,
+) -> Result<()> {
+    let mut pages = split::SplitOutput::new(dir, "html")?;
+    let mut entries = Vec::new();
+    for (((name, environment), relative_path), node) in names
+        .into_iter()
+        .zip(environments)
+        .zip(relative_paths)
+        .zip(nodes)
+    {
+        let page = html::package_page(&format!("move-tree: {}", name), &[node]);
+        let path = pages.write(&name, &environment, page.as_bytes())?;
+        entries.push(html::IndexEntry {
+            name,
+            environment,
+            relative_path,
+            file_name: path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        });
+    }
+
+    let index = dir.join("index.html");
+    std::fs::write(&index, html::index("move-tree", &entries))
+        .with_context(|| format!("Failed to write {}", index.display()))?;
+    println!("{}", index.display());
+    Ok(())
+}
+
+/// Labels are styled even when stdout is not a terminal (the page is usually redirected to a
+/// file), since their styles become CSS classes. `--no-color` still turns them off.
+fn use_html_colors(args: &CommonArgs) {
+    if !args.no_color {
+        colored::control::set_override(true);
+    }
+}
+
+fn print_html(names: &[String], nodes: &[html::Node]) {
+    println!(
+        "{}",
+        html::document(&format!("move-tree: {}", names.join(", ")), nodes)
+    );
+}
+
+async fn run_plugin(args: &TreeArgs, package_roots: &[PathBuf], plugin: &Path) -> Result<()> {
+    let mut shown = false;
+    for root in package_roots {
+        let output = compile_package_output(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let modules = select_package_modules(args, &output.compiled);
+        let package = PackageJson {
+            package_name: output.compiled.compiled_package_info.package_name.as_str(),
+            relative_path: package_path_label(&args.common, root),
+            environment: Some(&output.environment),
+            modules: &modules,
+        };
+        plugin::run(plugin, Some(root), &package)?;
+        shown |= modules.iter().any(|module| !is_empty_module(module));
+    }
+    check_not_empty(args, shown)
+}
+
+fn print_json(value: &impl Serialize, pretty: bool) -> Result<()> {
+    if pretty {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        println!("{}", serde_json::to_string(value)?);
+    }
+    Ok(())
+}
+
+async fn emit_sarif_report(package_roots: &[PathBuf], build: &BuildArgs) -> Result<()> {
+    let mut findings = Vec::new();
+
+    for root in package_roots {
+        let compiled = compile_package(root, build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        findings.extend(lints::check_compiled_package(root, &compiled));
+    }
+
+    print_sarif_report(&findings)
+}
+
+fn print_sarif_report(findings: &[lints::Finding]) -> Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&sarif::report(findings))?
+    );
+    Ok(())
+}
+
+async fn render_struct_graphs(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let nodes = struct_graph::build(root_modules(&compiled));
+        let package_name = compiled.compiled_package_info.package_name.as_str();
+        struct_graph::print(
+            args,
+            package_path_label(&args.common, root),
+            package_name,
+            &nodes,
+        );
+    }
+
+    Ok(())
+}
+
+/// A single DOT graph of the modules of all of `package_roots`. Which package each imported
+/// module belongs to is known from the dependencies each package is compiled with.
+async fn render_module_graph(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut graph = module_graph::ModuleGraph::default();
+    for root in package_roots {
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let package_name = compiled.compiled_package_info.package_name.as_str();
+        let owners = compiled
+            .deps_compiled_units
+            .iter()
+            .map(|(package, unit)| (&unit.unit.module, package.as_str()))
+            .chain(root_modules(&compiled).map(|module| (module, package_name)))
+            .map(|(module, package)| {
+                let key = (*module.address(), module.name().to_string());
+                (key, package.to_string())
+            })
+            .collect();
+        graph.add(package_name, root_modules(&compiled), &owners);
+    }
+
+    print!("{}", graph.render_dot());
+    Ok(())
+}
+
+async fn count_by_visibility(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let branches = Branches::from_args(&args.common);
+
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let modules = collect_all_visibilities(&args.select, root_modules(&compiled));
+        visibility::print_counts(
+            &branches,
+            package_path_label(&args.common, root),
+            compiled.compiled_package_info.package_name.as_str(),
+            &modules,
+        );
+    }
+
+    Ok(())
+}
+
+/// Every function regardless of `--visibility`, which would skew the counts, but still honoring
+/// `--filter` and `--exclude`
+fn collect_all_visibilities<'a>(
+    select: &SelectArgs,
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Vec<ModuleInfo> {
+    let modules = collect_modules(compiled_modules, VisibilityFilter::All, false);
+    filter_modules(modules, select.filter.as_ref(), select.exclude.as_ref())
+}
+
+async fn count_instructions(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let branches = Branches::from_args(&args.common);
+    let mut total = 0;
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        total += print_instruction_counts(
+            &branches,
+            package_path_label(&args.common, root).as_deref(),
+            compiled.compiled_package_info.package_name.as_str(),
+            &instruction_counts(root_modules(&compiled)),
+        );
+    }
+
+    if package_roots.len() > 1 {
+        println!();
+        println!(
+            "{} {} across {} packages",
+            palette().header.paint("total"),
+            format_instructions(total).bold(),
+            package_roots.len()
+        );
+    }
+    Ok(())
+}
+
+/// The number of instructions of the body of `function`, 0 for a native one
+fn instruction_count(function: &FunctionDefinition) -> usize {
+    function.code.as_ref().map_or(0, |code| code.code.len())
+}
+
+/// The number of instructions of all the functions of each of `modules`, sorted by module name
+fn instruction_counts<'a>(
+    modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Vec<(String, usize)> {
+    let mut counts = modules
+        .map(|module| {
+            let count = module.function_defs().iter().map(instruction_count).sum();
+            (module.name().to_string(), count)
+        })
+        .collect::<Vec<_>>();
+    counts.sort();
+    counts
+}
+
+/// Print the instruction count of each module and their total, which is returned
+fn print_instruction_counts(
+    branches: &Branches,
+    relative_path: Option<&str>,
+    name: &str,
+    counts: &[(String, usize)],
+) -> usize {
+    println!(
+        "{}",
+        render_package_header("package", relative_path, name, None)
+    );
+
+    let mut total = 0;
+    for (module, count) in counts {
+        total += count;
+        println!(
+            "{}{} {}: {}",
+            branches.branch(false),
+            palette().module.paint("module").bold(),
+            palette().module.paint(module),
+            format_instructions(*count)
+        );
+    }
+    println!(
+        "{}{}: {}",
+        branches.last,
+        "total".bold(),
+        format_instructions(total).bold()
+    );
+    total
+}
+
+fn format_instructions(count: usize) -> String {
+    match count {
+        1 => "1 instruction".to_string(),
+        n => format!("{} instructions", n),
+    }
+}
+
+async fn report_phantom_params(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let branches = Branches::from_args(&args.common);
+
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let datatypes = phantom::analyze(root_modules(&compiled));
+        phantom::print_report(
+            &branches,
+            package_path_label(&args.common, root),
+            compiled.compiled_package_info.package_name.as_str(),
+            &datatypes,
+        );
+    }
+
+    Ok(())
+}
+
+async fn group_functions_by_return_type(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let modules = select_package_modules(args, &compiled);
+        print_return_type_groups(
+            args,
+            package_path_label(&args.common, root).as_deref(),
+            compiled.compiled_package_info.package_name.as_str(),
+            &modules,
+        );
+    }
+
+    Ok(())
+}
+
+/// Print the functions of `modules` under their return type, as `module::function` without the
+/// return type that is already in the group. Types are sorted by name, with functions returning
+/// nothing last.
+fn print_return_type_groups(
+    args: &TreeArgs,
+    relative_path: Option<&str>,
+    name: &str,
+    modules: &[ModuleInfo],
+) {
+    let branches = Branches::from_args(&args.common);
+    println!(
+        "{}",
+        render_package_header("package", relative_path, name, None)
+    );
+
+    let mut groups = BTreeMap::<_, Vec<_>>::new();
+    for module in modules {
+        for function in &module.functions {
+            let returns = match function.returns.as_slice() {
+                [] => None,
+                [single] => Some(single.clone()),
+                several => Some(format!("({})", several.join(", "))),
+            };
+            groups
+                .entry((returns.is_none(), returns))
+                .or_default()
+                .push((module, function));
+        }
+    }
+
+    if groups.is_empty() {
+        println!("{}{}", branches.last, "(no functions)".dimmed());
+        return;
+    }
+
+    let signature = SignatureArgs {
+        no_returns: true,
+        ..args.signature
+    };
+    for (group_index, ((_, returns), functions)) in groups.iter().enumerate() {
+        let is_last_group = group_index + 1 == groups.len();
+        let label = match returns {
+            Some(returns) => palette().returns.paint(returns).to_string(),
+            None => "(nothing)".dimmed().to_string(),
+        };
+        println!(
+            "{}{} {}",
+            branches.branch(is_last_group),
+            label,
+            format!("({})", functions.len()).dimmed()
+        );
+
+        let child_prefix = branches.indent(is_last_group);
+        for (function_index, (module, function)) in functions.iter().enumerate() {
+            println!(
+                "{}{}{}::{}",
+                child_prefix,
+                branches.branch(function_index + 1 == functions.len()),
+                palette().module.paint(&module.name),
+                render_function(function, signature)
+            );
+        }
+    }
+}
+
+async fn group_by_ability(args: &TreeArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let branches = Branches::from_args(&args.common);
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let compiled = compile_package(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let modules = select_modules(&args.select, &ability_detail(), root_modules(&compiled));
+        print_ability_groups(
+            &branches,
+            package_path_label(&args.common, root).as_deref(),
+            compiled.compiled_package_info.package_name.as_str(),
+            &modules,
+        );
+    }
+
+    Ok(())
+}
+
+/// `--group-by-ability` needs the datatypes of every module, whatever `--structs` says
+fn ability_detail() -> DetailArgs {
+    DetailArgs {
+        structs: true,
+        ..DetailArgs::default()
+    }
+}
+
+/// Print the structs and enums of `modules` under their ability set, as `module::Type`. Sets are
+/// sorted by their abilities, with types that have none last.
+fn print_ability_groups(
+    branches: &Branches,
+    relative_path: Option<&str>,
+    name: &str,
+    modules: &[ModuleInfo],
+) {
+    println!(
+        "{}",
+        render_package_header("package", relative_path, name, None)
+    );
+
+    let mut groups = BTreeMap::<_, Vec<_>>::new();
+    for module in modules {
+        for datatype in &module.structs {
+            let abilities = format_abilities(datatype.abilities);
+            groups
+                .entry((abilities.is_empty(), abilities))
+                .or_default()
+                .push((module, datatype));
+        }
+    }
+
+    if groups.is_empty() {
+        println!("{}{}", branches.last, "(no structs or enums)".dimmed());
+        return;
+    }
+
+    for (group_index, ((_, abilities), datatypes)) in groups.iter().enumerate() {
+        let is_last_group = group_index + 1 == groups.len();
+        let label = if abilities.is_empty() {
+            "(no abilities)".dimmed().to_string()
+        } else {
+            palette().types.paint(abilities).to_string()
+        };
+        println!(
+            "{}{} {}",
+            branches.branch(is_last_group),
+            label,
+            format!("({})", datatypes.len()).dimmed()
+        );
+
+        let child_prefix = branches.indent(is_last_group);
+        for (datatype_index, (module, datatype)) in datatypes.iter().enumerate() {
+            println!(
+                "{}{}{} {}::{}",
+                child_prefix,
+                branches.branch(datatype_index + 1 == datatypes.len()),
+                palette().keyword.paint(datatype.kind),
+                palette().module.paint(&module.name),
+                datatype.name.bold()
+            );
+        }
+    }
+}
+
+async fn check_entry_signatures(
+    args: &TreeArgs,
+    package_roots: &[PathBuf],
+    path: &Path,
+) -> Result<()> {
+    let [root] = package_roots else {
+        bail!(
+            "`--entry-signature-compat` needs exactly one package, found {}",
+            package_roots.len()
+        );
+    };
+    let expectations = compat::read(path)?;
+
+    let compiled = compile_package(root, &args.common.build)
+        .await
+        .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+    let modules = collect_modules(root_modules(&compiled), VisibilityFilter::All, false);
+    print_entry_compat(
+        &Branches::from_args(&args.common),
+        package_path_label(&args.common, root).as_deref(),
+        compiled.compiled_package_info.package_name.as_str(),
+        path,
+        &expectations,
+        &modules,
+    )
+}
+
+/// Print the expected entry functions that `modules` do not match, with the expected and found
+/// signatures of those that changed, and fail if there is any
+fn print_entry_compat(
+    branches: &Branches,
+    relative_path: Option<&str>,
+    name: &str,
+    path: &Path,
+    expectations: &compat::Expectations,
+    modules: &[ModuleInfo],
+) -> Result<()> {
+    println!(
+        "{}",
+        render_package_header("package", relative_path, name, None)
+    );
+
+    let mismatches = compat::check(expectations, modules);
+    if mismatches.is_empty() {
+        println!(
+            "{}{}",
+            branches.last,
+            format!(
+                "(all {} expected entry functions match)",
+                expectations.entry_functions.len()
+            )
+            .dimmed()
+        );
+        return Ok(());
+    }
+
+    for (index, mismatch) in mismatches.iter().enumerate() {
+        let is_last = index + 1 == mismatches.len();
+        let branch = branches.branch(is_last);
+        match mismatch {
+            compat::Mismatch::Missing(expected) => println!(
+                "{}{} {}::{}",
+                branch,
+                palette().removed.paint("missing"),
+                palette().module.paint(&expected.module),
+                expected.name.bold()
+            ),
+            compat::Mismatch::NotEntry(expected) => println!(
+                "{}{} {}::{}",
+                branch,
+                palette().removed.paint("not entry"),
+                palette().module.paint(&expected.module),
+                expected.name.bold()
+            ),
+            compat::Mismatch::Changed { expected, found } => {
+                println!(
+                    "{}{} {}::{}",
+                    branch,
+                    palette().changed.paint("changed"),
+                    palette().module.paint(&expected.module),
+                    expected.name.bold()
+                );
+                let child_prefix = branches.indent(is_last);
+                println!(
+                    "{}{}{} {}",
+                    child_prefix,
+                    branches.branch(false),
+                    "expected".dimmed(),
+                    expected.signature()
+                );
+                println!(
+                    "{}{}{}    {}",
+                    child_prefix,
+                    branches.branch(true),
+                    "found".dimmed(),
+                    compat::signature(&found.type_params, &found.params, &found.returns)
+                );
+            }
+        }
+    }
+
+    bail!(
+        "{} of {} expected entry functions do not match {}",
+        mismatches.len(),
+        expectations.entry_functions.len(),
+        path.display()
+    );
+}
+
+async fn diff_packages(args: &DiffArgs) -> Result<()> {
+    if let Some(package_id) = &args.verify_onchain {
+        return verify_onchain(args, package_id).await;
+    }
+    if let Some(tag) = &args.since_tag {
+        return diff_since_tag(args, tag).await;
+    }
+
+    let [old_path, new_path] = args.common.path.as_slice() else {
+        bail!(
+            "`diff` takes two paths, the old and the new version of a package, got {}",
+            args.common.path.len()
+        );
+    };
+    let old_roots = find_move_packages(old_path, args.common.respect_gitignore)?;
+    let new_roots = find_move_packages(new_path, args.common.respect_gitignore)?;
+    let (old_root, new_root) = match (old_roots.as_slice(), new_roots.as_slice()) {
+        ([old_root], [new_root]) => (old_root, new_root),
+        _ => bail!(
+            "`diff` needs exactly one package on each side, found {} under {} and {} under {}",
+            old_roots.len(),
+            old_path.display(),
+            new_roots.len(),
+            new_path.display()
+        ),
+    };
+    diff_package_roots(args, old_root, new_root, None).await
+}
+
+async fn diff_since_tag(args: &DiffArgs, tag: &str) -> Result<()> {
+    let [path] = args.common.path.as_slice() else {
+        bail!(
+            "`--since-tag` takes a single local package, got {} paths",
+            args.common.path.len()
+        );
+    };
+    let roots = find_move_packages(path, args.common.respect_gitignore)?;
+    let [root] = roots.as_slice() else {
+        bail!(
+            "`--since-tag` needs exactly one package, found {} under {}",
+            roots.len(),
+            path.display()
+        );
+    };
+
+    let checkout = git::checkout_tag(root, tag)?;
+    diff_package_roots(args, &checkout.package_path, root, Some(tag)).await
+}
+
+/// Diff the packages at `old_root` and `new_root`. `old_label` names the old side in the header
+/// instead of its package name.
+async fn diff_package_roots(
+    args: &DiffArgs,
+    old_root: &Path,
+    new_root: &Path,
+    old_label: Option<&str>,
+) -> Result<()> {
+    let build = &args.common.build;
+    let old = compile_package(old_root, build)
+        .await
+        .with_context(|| format!("Failed to compile Move package at {}", old_root.display()))?;
+    let new = compile_package(new_root, build)
+        .await
+        .with_context(|| format!("Failed to compile Move package at {}", new_root.display()))?;
+    let old_name = old.compiled_package_info.package_name.as_str();
+    let old_name = old_label.map_or_else(
+        || old_name.to_string(),
+        |tag| format!("{}@{}", old_name, tag),
+    );
+    let new_name = new.compiled_package_info.package_name.as_str();
+    let branches = Branches::from_args(&args.common);
+
+    if args.compare_visibility {
+        let old_modules = collect_all_visibilities(&args.select, root_modules(&old));
+        let new_modules = collect_all_visibilities(&args.select, root_modules(&new));
+        let changes = diff::visibility_changes(&old_modules, &new_modules);
+        print_visibility_changes(&branches, &old_name, new_name, &changes);
+        return Ok(());
+    }
+
+    let mut old_modules = select_modules(&args.select, &DetailArgs::default(), root_modules(&old));
+    let mut new_modules = select_modules(&args.select, &DetailArgs::default(), root_modules(&new));
+    if args.normalize_generics {
+        normalize_generics(&mut old_modules, root_modules(&old));
+        normalize_generics(&mut new_modules, root_modules(&new));
+    }
+
+    let diffs = diff::diff_modules(&old_modules, &new_modules, args.only_changed_sigs);
+    print_diff(
+        &branches,
+        &old_name,
+        new_name,
+        &diffs,
+        args.only_changed_sigs,
+    );
+    Ok(())
+}
+
+async fn verify_onchain(args: &DiffArgs, package_id: &str) -> Result<()> {
+    let [path] = args.common.path.as_slice() else {
+        bail!(
+            "`--verify-onchain` takes a single local package, got {} paths",
+            args.common.path.len()
+        );
+    };
+    if !chain::looks_like_address(package_id) {
+        bail!("`{}` is not a valid package id", package_id);
+    }
+    let roots = find_move_packages(path, args.common.respect_gitignore)?;
+    let [root] = roots.as_slice() else {
+        bail!(
+            "`--verify-onchain` needs exactly one package, found {} under {}",
+            roots.len(),
+            path.display()
+        );
+    };
+
+    let local = compile_package(root, &args.common.build)
+        .await
+        .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+    let onchain = chain::fetch_package_modules(&args.rpc_url, package_id).await?;
+
+    if args.compare_visibility {
+        let onchain_modules = collect_all_visibilities(&args.select, onchain.iter());
+        let local_modules = collect_all_visibilities(&args.select, root_modules(&local));
+        let changes = diff::visibility_changes(&onchain_modules, &local_modules);
+        print_visibility_changes(
+            &Branches::from_args(&args.common),
+            package_id,
+            local.compiled_package_info.package_name.as_str(),
+            &changes,
+        );
+        if !changes.is_empty() {
+            bail!(
+                "package at {} changes the visibility of functions of on-chain package {}",
+                root.display(),
+                package_id
+            );
+        }
+        return Ok(());
+    }
+
+    let detail = DetailArgs {
+        structs: true,
+        ..DetailArgs::default()
+    };
+    let mut onchain_modules = select_modules(&args.select, &detail, onchain.iter());
+    let mut local_modules = select_modules(&args.select, &detail, root_modules(&local));
+    if args.normalize_generics {
+        normalize_generics(&mut onchain_modules, onchain.iter());
+        normalize_generics(&mut local_modules, root_modules(&local));
+    }
+
+    let diffs = diff::diff_modules(&onchain_modules, &local_modules, args.only_changed_sigs);
+    print_diff(
+        &Branches::from_args(&args.common),
+        package_id,
+        local.compiled_package_info.package_name.as_str(),
+        &diffs,
+        args.only_changed_sigs,
+    );
+    if !diffs.is_empty() {
+        bail!(
+            "package at {} does not match on-chain package {}",
+            root.display(),
+            package_id
+        );
+    }
+    Ok(())
+}
+
+/// Rename the type parameters of each function of `modules` after the order in which its
+/// parameters, then its return types, first use them (unused ones last), so that functions that
+/// only differ in the order of their type parameters end up with the same signature
+fn normalize_generics<'a>(
+    modules: &mut [ModuleInfo],
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) {
+    for compiled in compiled_modules {
+        let Some(module) = modules
+            .iter_mut()
+            .find(|module| module.name == compiled.name().as_str())
+        else {
+            continue;
+        };
+        for def in compiled.function_defs() {
+            let handle = compiled.function_handle_at(def.function);
+            let name = compiled.identifier_at(handle.name).as_str();
+            let Some(function) = module.functions.iter_mut().find(|f| f.name == name) else {
+                continue;
+            };
+
+            let params = &compiled.signature_at(handle.parameters).0;
+            let returns = &compiled.signature_at(handle.return_).0;
+            let mut order = Vec::new();
+            let used = params
+                .iter()
+                .chain(returns)
+                .flat_map(SignatureToken::preorder_traversal)
+                .filter_map(|token| match token {
+                    SignatureToken::TypeParameter(index) => Some(*index as usize),
+                    _ => None,
+                });
+            for index in used.chain(0..handle.type_parameters.len()) {
+                if !order.contains(&index) {
+                    order.push(index);
+                }
+            }
+            let mut names = vec![String::new(); order.len()];
+            for (position, index) in order.into_iter().enumerate() {
+                names[index] = format!("T{}", position);
+            }
+
+            let format_types = |tokens: &[SignatureToken]| {
+                tokens
+                    .iter()
+                    .map(|token| format_named_token(compiled, &names, token))
+                    .collect()
+            };
+            function.params = format_types(params);
+            function.returns = format_types(returns);
+        }
+    }
+}
+
+fn print_diff(
+    branches: &Branches,
+    old_name: &str,
+    new_name: &str,
+    diffs: &[diff::ModuleDiff<'_>],
+    only_changed_sigs: bool,
+) {
+    println!(
+        "{} {} -> {}",
+        palette().header.paint("diff"),
+        old_name.bold(),
+        new_name.bold()
+    );
+
+    if diffs.is_empty() {
+        let empty = if only_changed_sigs {
+            "(no changed signatures)"
+        } else {
+            "(no changes)"
+        };
+        println!("{}{}", branches.last, empty.dimmed());
+        return;
+    }
+
+    for (module_index, module) in diffs.iter().enumerate() {
+        let is_last_module = module_index + 1 == diffs.len();
+        println!(
+            "{}{} {}",
+            branches.branch(is_last_module),
+            palette().module.paint("module").bold(),
+            palette().module.paint(&module.name)
+        );
+
+        let lines = module
+            .changes
+            .iter()
+            .map(|change| render_change(change, render_function))
+            .chain(
+                module
+                    .struct_changes
+                    .iter()
+                    .map(|change| render_change(change, render_struct)),
+            )
+            .collect::<Vec<_>>();
+        let child_prefix = branches.indent(is_last_module);
+        for (line_index, line) in lines.iter().enumerate() {
+            let branch = branches.branch(line_index + 1 == lines.len());
+            println!("{}{}{}", child_prefix, branch, line);
+        }
+    }
+}
+
+fn print_visibility_changes(
+    branches: &Branches,
+    old_name: &str,
+    new_name: &str,
+    modules: &[diff::ModuleVisibilityChanges<'_>],
+) {
+    println!(
+        "{} {} -> {}",
+        palette().header.paint("visibility"),
+        old_name.bold(),
+        new_name.bold()
+    );
+
+    if modules.is_empty() {
+        println!("{}{}", branches.last, "(no visibility changes)".dimmed());
+        return;
+    }
+
+    for (module_index, module) in modules.iter().enumerate() {
+        let is_last_module = module_index + 1 == modules.len();
+        println!(
+            "{}{} {}",
+            branches.branch(is_last_module),
+            palette().module.paint("module").bold(),
+            palette().module.paint(&module.name)
+        );
+
+        let child_prefix = branches.indent(is_last_module);
+        for (index, change) in module.changes.iter().enumerate() {
+            let mut line = format!(
+                "{}  {}  {}  {}",
+                palette().function.paint(&change.function),
+                source_visibility(change.old),
+                palette().keyword.paint("=>"),
+                source_visibility(change.new)
+            );
+            if change.is_breaking() {
+                line.push_str(&format!(" {}", palette().removed.paint("[breaking]")));
+            }
+            let branch = branches.branch(index + 1 == module.changes.len());
+            println!("{}{}{}", child_prefix, branch, line);
+        }
+    }
+}
+
+/// `visibility` as it is written in source
+fn source_visibility(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "public",
+        Visibility::Friend => "public(package)",
+        Visibility::Private => "private",
+    }
+}
+
+fn render_change<T>(change: &diff::Change<'_, T>, render: fn(&T) -> String) -> String {
+    match change {
+        diff::Change::Added(item) => format!("{} {}", palette().added.paint("+"), render(item)),
+        diff::Change::Removed(item) => format!("{} {}", palette().removed.paint("-"), render(item)),
+        diff::Change::Changed { old, new } => format!(
+            "{} {}  {}  {}",
+            palette().changed.paint("~"),
+            render(old),
+            palette().keyword.paint("=>"),
+            render(new)
+        ),
+    }
+}
+
+async fn describe_function(
+    args: &TreeArgs,
+    package_roots: &[PathBuf],
+    selector: &str,
+) -> Result<()> {
+    let (module_name, function_name) = parse_function_selector(selector)?;
+
+    let mut packages = Vec::new();
+    for root in package_roots {
+        packages.push(
+            compile_package(root, &args.common.build)
+                .await
+                .with_context(|| format!("Failed to compile Move package at {}", root.display()))?,
+        );
+    }
+
+    print_function_detail(
+        &Branches::from_args(&args.common),
+        packages.iter().flat_map(root_modules),
+        module_name,
+        function_name,
+    )
+}
+
+/// Split `MODULE::NAME` (optionally prefixed with a package or address, which is ignored)
+fn parse_function_selector(selector: &str) -> Result<(&str, &str)> {
+    match selector.rsplit_once("::") {
+        Some((module, name)) if !module.is_empty() && !name.is_empty() => {
+            let module = module.rsplit("::").next().unwrap_or(module);
+            Ok((module, name))
+        }
+        _ => bail!("`{}` is not of the form MODULE::NAME", selector),
+    }
+}
+
+fn print_function_detail<'a>(
+    branches: &Branches,
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+    module_name: &str,
+    function_name: &str,
+) -> Result<()> {
+    let modules = compiled_modules.collect::<Vec<_>>();
+    let function_name_of = |module: &CompiledModule, def: &FunctionDefinition| {
+        module
+            .identifier_at(module.function_handle_at(def.function).name)
+            .to_string()
+    };
+
+    let found = modules
+        .iter()
+        .filter(|module| module.name().as_str() == module_name)
+        .find_map(|module| {
+            module
+                .function_defs()
+                .iter()
+                .find(|def| function_name_of(module, def) == function_name)
+                .map(|def| (*module, def))
+        });
+
+    let Some((module, def)) = found else {
+        let wanted = format!("{}::{}", module_name, function_name);
+        let mut suggestions = modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .function_defs()
+                    .iter()
+                    .map(move |def| format!("{}::{}", module.name(), function_name_of(module, def)))
+            })
+            .filter_map(|candidate| {
+                let distance = edit_distance(&candidate, &wanted);
+                let same_name = candidate.ends_with(&format!("::{}", function_name));
+                (distance <= 3 || same_name).then_some((distance, candidate))
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort();
+        suggestions.truncate(5);
+
+        if suggestions.is_empty() {
+            bail!("function `{}` not found", wanted);
+        }
+        bail!(
+            "function `{}` not found; did you mean {}?",
+            wanted,
+            suggestions
+                .iter()
+                .map(|(_, candidate)| format!("`{}`", candidate))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+
+    let handle = module.function_handle_at(def.function);
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    let type_params = handle
+        .type_parameters
+        .iter()
+        .enumerate()
+        .map(|(index, abilities)| {
+            if *abilities == AbilitySet::EMPTY {
+                format!("T{}", index)
+            } else {
+                format!("T{}: {}", index, format_abilities(*abilities))
+            }
+        })
+        .collect::<Vec<_>>();
+    let params = module
+        .signature_at(handle.parameters)
+        .0
+        .iter()
+        .enumerate()
+        .map(|(index, token)| format!("{}: {}", index, format_signature_token(module, token)))
+        .collect::<Vec<_>>();
+    let returns = module
+        .signature_at(handle.return_)
+        .0
+        .iter()
+        .map(|token| format_signature_token(module, token))
+        .collect::<Vec<_>>();
+    let acquires = def
+        .acquires_global_resources
+        .iter()
+        .map(|index| {
+            let handle = module.struct_def_at(*index).struct_handle;
+            module
+                .identifier_at(module.datatype_handle_at(handle).name)
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+
+    // Scalar properties are rendered inline, lists as children (or `(none)`)
+    let sections: Vec<(String, Option<Vec<String>>)> = vec![
+        (
+            format!("visibility: {}", visibility_label(def.visibility)),
+            None,
+        ),
+        (format!("entry: {}", yes_no(def.is_entry)), None),
+        (format!("native: {}", yes_no(def.code.is_none())), None),
+        ("type parameters".to_string(), Some(type_params)),
+        ("parameters".to_string(), Some(params)),
+        ("returns".to_string(), Some(returns)),
+        ("acquires".to_string(), Some(acquires)),
+    ];
+
+    println!(
+        "{} {}::{}",
+        palette().keyword.paint("fun"),
+        palette().module.paint(module.name().as_str()),
+        palette().function.paint(function_name)
+    );
+    for (index, (heading, children)) in sections.iter().enumerate() {
+        let is_last = index + 1 == sections.len();
+        println!("{}{}", branches.branch(is_last), heading);
+
+        let Some(children) = children else {
+            continue;
+        };
+        let child_prefix = branches.indent(is_last);
+        if children.is_empty() {
+            println!("{}{}{}", child_prefix, branches.last, "(none)".dimmed());
+        }
+        for (child_index, child) in children.iter().enumerate() {
+            let is_last_child = child_index + 1 == children.len();
+            println!(
+                "{}{}{}",
+                child_prefix,
+                branches.branch(is_last_child),
+                palette().types.paint(child)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Levenshtein distance, used to suggest similarly named functions
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+async fn find_type(package_roots: &[PathBuf], type_name: &str, build: &BuildArgs) -> Result<()> {
+    let mut definitions = Vec::new();
+
+    for root in package_roots {
+        let compiled = compile_package(root, build)
+            .await
+            .with_context(|| format!("Failed to compile Move package at {}", root.display()))?;
+        let package_name = compiled.compiled_package_info.package_name.as_str();
+        definitions.extend(find_type_definitions(
+            package_name,
+            root_modules(&compiled),
+            type_name,
+        ));
+    }
+
+    print_type_definitions(type_name, &definitions);
+    Ok(())
+}
+
+/// Find the structs and enums named `type_name` defined by `compiled_modules`
+fn find_type_definitions<'a>(
+    package: &str,
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+    type_name: &str,
+) -> Vec<TypeDefinition> {
+    let mut definitions = Vec::new();
+
+    for module in compiled_modules {
+        let handles = module
+            .struct_defs()
+            .iter()
+            .map(|def| ("struct", def.struct_handle))
+            .chain(
+                module
+                    .enum_defs()
+                    .iter()
+                    .map(|def| ("enum", def.enum_handle)),
+            );
+
+        for (kind, handle_index) in handles {
+            let handle = module.datatype_handle_at(handle_index);
+            let name = module.identifier_at(handle.name);
+            if name.as_str() != type_name {
+                continue;
+            }
+
+            definitions.push(TypeDefinition {
+                package: package.to_string(),
+                module: module.name().to_string(),
+                name: name.to_string(),
+                kind,
+                abilities: handle.abilities,
+            });
+        }
+    }
+
+    definitions
+        .sort_by(|a, b| (&a.package, &a.module, &a.name).cmp(&(&b.package, &b.module, &b.name)));
+    definitions
+}
+
+fn print_type_definitions(type_name: &str, definitions: &[TypeDefinition]) {
+    if definitions.is_empty() {
+        println!(
+            "{}",
+            format!("(no definitions of `{}` found)", type_name).dimmed()
+        );
+        return;
+    }
+
+    for definition in definitions {
+        let mut line = format!(
+            "{} {}::{}::{}",
+            palette().keyword.paint(definition.kind),
+            definition.package.bold(),
+            palette().module.paint(&definition.module),
+            palette().datatype.paint(&definition.name)
+        );
+        if definition.abilities != AbilitySet::EMPTY {
+            line.push_str(&format!(
+                " {} {}",
+                palette().keyword.paint("has"),
+                format_abilities(definition.abilities)
+            ));
+        }
+        println!("{}", line);
+    }
+}
+
+fn format_abilities(abilities: AbilitySet) -> String {
+    abilities
+        .into_iter()
+        .map(|ability| ability.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The packages found under any of `paths`, without duplicates
+fn find_all_packages(paths: &[PathBuf], respect_gitignore: bool) -> Result<Vec<PathBuf>> {
+    let mut roots = BTreeSet::new();
+    for path in paths {
+        roots.extend(find_move_packages(path, respect_gitignore)?);
+    }
+
+    Ok(roots.into_iter().collect())
+}
+
+/// The `path` argument `package_path` was found under, which its displayed path is relative to.
+/// Picks the closest one when arguments are nested.
+fn scan_root<'a>(args: &'a CommonArgs, package_path: &Path) -> &'a Path {
+    args.path
+        .iter()
+        .filter(|path| package_path.starts_with(path))
+        .max_by_key(|path| path.components().count())
+        .unwrap_or(&args.path[0])
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Why `path` could not be read, with a hint for the common cases
+fn access_error(path: &Path, err: io::Error) -> anyhow::Error {
+    let (reason, hint) = match err.kind() {
+        io::ErrorKind::NotFound => ("no such path", "check it for typos"),
+        io::ErrorKind::PermissionDenied => (
+            "permission denied",
+            "check the permissions of the path and its parent directories",
+        ),
+        io::ErrorKind::NotADirectory => (
+            "one of its parents is not a directory",
+            "check that every component but the last is a directory",
+        ),
+        _ => return anyhow!("Unable to access {}: {}", path.display(), err),
+    };
+    anyhow!("Unable to access {}: {}; {}", path.display(), reason, hint)
+}
+
+/// The packages under `path`, skipping build output and VCS directories, and with
+/// `respect_gitignore` whatever the `.gitignore` and `.ignore` files under it exclude
+fn find_move_packages(path: &Path, respect_gitignore: bool) -> Result<Vec<PathBuf>> {
+    let metadata = std::fs::metadata(path).map_err(|err| access_error(path, err))?;
+
+    let mut roots = BTreeSet::new();
+
+    if metadata.is_file() {
+        if path
+            .file_name()
+            .map(|name| name == "Move.toml")
+            .unwrap_or(false)
+        {
+            if let Some(parent) = path.parent() {
+                roots.insert(parent.to_path_buf());
+            }
+        }
+    } else {
+        // Ignore files apply outside of a git checkout too, such as in an extracted archive
+        let mut ignore_files = gitignore::IgnoreFiles::default();
+        for entry in WalkDir::new(path)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| {
+                !should_skip_dir(entry)
+                    && !(respect_gitignore
+                        && ignore_files.is_ignored(path, entry.path(), entry.file_type().is_dir()))
+            })
+        {
+            let entry = entry?;
+            if entry.file_type().is_file()
+                && entry.file_name() == "Move.toml"
+                && entry.path().parent().is_some()
+            {
+                roots.insert(entry.path().parent().unwrap().to_path_buf());
+            }
+        }
+    }
+
+    Ok(roots.into_iter().collect())
+}
+
+fn should_skip_dir(entry: &DirEntry) -> bool {
+    if !entry.file_type().is_dir() {
+        return false;
+    }
+
+    let name = entry.file_name().to_string_lossy();
+    matches!(name.as_ref(), ".git" | "target" | "build" | "node_modules")
+}
+
+/// Parse the Move.toml of the package at `path` as plain TOML. Syntax errors name the manifest
+/// and the line and column the parser stopped at, which the package system does not report.
+fn read_manifest(path: &Path) -> Result<toml::Value> {
+    let manifest_path = path.join("Move.toml");
+    let contents = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+
+    toml::from_str(&contents).map_err(|err| {
+        let position = err
+            .span()
+            .map(|span| {
+                let before = &contents[..span.start.min(contents.len())];
+                let line = before.matches('\n').count() + 1;
+                let column = before
+                    .rsplit('\n')
+                    .next()
+                    .map_or(0, |line| line.chars().count())
+                    + 1;
+                format!(":{}:{}", line, column)
+            })
+            .unwrap_or_default();
+        anyhow!(
+            "invalid manifest {}{}: {}",
+            manifest_path.display(),
+            position,
+            err.message()
+        )
+    })
+}
+
+/// The environments to try, in order: those of `package_environments`, with the ones pinned in
+/// Move.lock first when neither `env` nor the fallback decided them
+fn environments_to_try(path: &Path, env: &[String]) -> Result<(Vec<Environment>, bool)> {
+    let (mut envs, synthetic) = package_environments(path, env)?;
+    if env.is_empty() && !synthetic {
+        // The lockfile records the environments the package was last resolved for, which are the
+        // ones its author builds with
+        let pinned = lockfile_environments(path);
+        envs.sort_by_key(|environment| !pinned.contains(environment.name()));
+    }
+    Ok((envs, synthetic))
+}
+
+/// The environments of the package: only those of `env` when it names any, otherwise those of the
+/// manifest. Packages whose manifest has no `[environments]` table (e.g. pure libraries) get a
+/// synthetic environment that has no chain-specific settings; the flag is set in that case so
+/// errors can point out what is missing.
+fn package_environments(path: &Path, env: &[String]) -> Result<(Vec<Environment>, bool)> {
+    // `environments` skips manifests it cannot parse, and loading the package later fails
+    // without saying where, so check the syntax up front
+    let manifest = read_manifest(path)
+        .with_context(|| format!("Failed to read environments for {}", path.display()))?;
+    // `environments` always adds the default ones, so whether the package declares any is only
+    // known from its manifest
+    let declares_environments = manifest
+        .get("environments")
+        .and_then(|environments| environments.as_table())
+        .is_some_and(|environments| !environments.is_empty());
+    let envs = RootPackage::<SuiFlavor>::environments(path)
+        .with_context(|| format!("Failed to read environments for {}", path.display()))?;
+
+    if !env.is_empty() {
+        let mut selected = Vec::new();
+        for name in env {
+            let Some(id) = envs.get(name) else {
+                let available = envs.keys().cloned().collect::<Vec<_>>();
+                bail!(
+                    "environment `{}` is not defined for {} (available: {})",
+                    name,
+                    path.display(),
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                );
+            };
+            selected.push(Environment::new(name.to_string(), id.clone()));
+        }
+        return Ok((selected, false));
+    }
+
+    if !declares_environments {
+        return Ok((vec![vanilla::default_environment()], true));
+    }
+
+    let envs = envs
+        .into_iter()
+        .map(|(name, id)| Environment::new(name, id))
+        .collect::<Vec<_>>();
+    Ok((envs, false))
+}
+
+/// The environments with a `[pinned.<environment>]` table in the package's Move.lock. Empty when
+/// there is no lockfile, or it cannot be read: the package system reports that when it loads the
+/// package.
+fn lockfile_environments(path: &Path) -> BTreeSet<String> {
+    let Ok(contents) = std::fs::read_to_string(path.join("Move.lock")) else {
+        return BTreeSet::new();
+    };
+    let Ok(lockfile) = toml::from_str::<toml::Value>(&contents) else {
+        return BTreeSet::new();
+    };
+
+    lockfile
+        .get("pinned")
+        .and_then(toml::Value::as_table)
+        .map(|pinned| pinned.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// A compiled package along with how it was produced
+struct CompileOutput {
+    compiled: CompiledPackage,
+    /// The environment the package was compiled for
+    environment: String,
+    /// Compiler warnings, rendered the way `sui move build` prints them
+    warnings: String,
+}
+
+async fn compile_package(path: &Path, build: &BuildArgs) -> Result<CompiledPackage> {
+    Ok(compile_package_output(path, build).await?.compiled)
+}
+
+async fn compile_package_output(path: &Path, build: &BuildArgs) -> Result<CompileOutput> {
+    let build_config = build.profile.build_config();
+    let (envs, synthetic) = environments_to_try(path, &build.env)?;
+
+    let mut last_error = None;
+
+    for env in envs {
+        let name = env.name().clone();
+        match compile_for_environment(path, &env, &build_config).await {
+            Ok((compiled, warnings)) => {
+                return Ok(CompileOutput {
+                    compiled,
+                    environment: name,
+                    warnings,
+                });
+            }
+            Err(err) => {
+                last_error = Some((name, err));
+            }
+        }
+    }
+
+    if let Some((name, err)) = last_error {
+        if synthetic {
+            return Err(anyhow!(
+                "package declares no environments and could not be compiled without one ({}); \
+                 add an `[environments]` entry to its Move.toml",
+                err
+            ));
+        }
+        Err(anyhow!(
+            "unable to compile package for any environment; last attempt with `{}` failed: {}",
+            name,
+            err
+        ))
+    } else {
+        Err(anyhow!(
+            "no environments available to compile package at {}",
+            path.display()
+        ))
+    }
+}
+
+/// Compile with our own driver instead of `BuildConfig::compile_package`, which reports
+/// diagnostics straight to stderr (and exits on errors), so that they can be captured
+async fn compile_for_environment(
+    path: &Path,
+    env: &Environment,
+    build_config: &BuildConfig,
+) -> Result<(CompiledPackage, String)> {
+    let root_package =
+        RootPackage::<SuiFlavor>::load(path, env.clone(), build_config.mode_set()).await?;
+    let color = colored::control::SHOULD_COLORIZE.should_colorize();
+    let mut warnings = Vec::new();
+
+    let compiled = BuildPlan::create(&root_package, build_config)?.compile_with_driver(
+        &mut io::sink(),
+        |compiler| {
+            let (files, units) = compiler.build()?;
+            match units {
+                Ok((units, diags)) => {
+                    warnings = report_diagnostics_to_buffer(&files, diags, color);
+                    Ok((files, units))
+                }
+                Err(diags) => bail!(
+                    "{}",
+                    String::from_utf8_lossy(&report_diagnostics_to_buffer(&files, diags, false))
+                ),
+            }
+        },
+    )?;
+
+    Ok((compiled, String::from_utf8_lossy(&warnings).into_owned()))
+}
+
+fn print_warnings(branches: &Branches, warnings: &str) {
+    println!("{}", palette().warning.paint("warnings:").bold());
+    if warnings.trim().is_empty() {
+        println!("{}{}", branches.last, "(none)".dimmed());
+    } else {
+        println!("{}", warnings.trim_end());
+    }
+}
+
+/// The functions of `modules` that `--warn-large-functions` flagged, as `module::function` with
+/// their number of instructions
+fn large_functions<'a>(modules: impl IntoIterator<Item = &'a ModuleInfo>) -> Vec<(String, usize)> {
+    modules
+        .into_iter()
+        .flat_map(|module| {
+            module.functions.iter().filter_map(|function| {
+                let instructions = function.instructions?;
+                Some((format!("{}::{}", module.name, function.name), instructions))
+            })
+        })
+        .collect()
+}
+
+/// The functions over `threshold` instructions, largest first
+fn print_large_functions(branches: &Branches, threshold: usize, large: &[(String, usize)]) {
+    println!(
+        "{}",
+        palette()
+            .warning
+            .paint(&format!(
+                "large functions (over {} instructions):",
+                threshold
+            ))
+            .bold()
+    );
+    if large.is_empty() {
+        println!("{}{}", branches.last, "(none)".dimmed());
+    }
+    let mut large = large.iter().collect::<Vec<_>>();
+    large.sort_by(|(left_name, left), (right_name, right)| {
+        right.cmp(left).then_with(|| left_name.cmp(right_name))
+    });
+    for (index, (name, instructions)) in large.iter().enumerate() {
+        println!(
+            "{}{} {}",
+            branches.branch(index + 1 == large.len()),
+            name.bold(),
+            format!("({} instructions)", instructions).dimmed()
+        );
+    }
+}
+
+fn print_findings(branches: &Branches, findings: &[lints::Finding]) {
+    println!("{}", palette().warning.paint("lints:").bold());
+    if findings.is_empty() {
+        println!("{}{}", branches.last, "(none)".dimmed());
+    }
+    for (index, finding) in findings.iter().enumerate() {
+        let branch = branches.branch(index + 1 == findings.len());
+        let severity = match finding.rule.severity {
+            lints::Severity::Warning => palette().warning.paint("warning"),
+            lints::Severity::Note => palette().note.paint("note"),
+        };
+        let mut line = format!(
+            "{}{}[{}] {}",
+            branch, severity, finding.rule.id, finding.message
+        );
+        if let Some(location) = &finding.location {
+            let location = format!(
+                "({}:{}:{})",
+                location.file.display(),
+                location.line,
+                location.column
+            );
+            line.push_str(&format!(" {}", location.dimmed()));
+        }
+        println!("{}", line);
+    }
+}
+
+async fn load_dependency_graph(path: &Path, build: &BuildArgs) -> Result<RootPackage<SuiFlavor>> {
+    let modes = build.profile.build_config().mode_set();
+    let (envs, synthetic) = environments_to_try(path, &build.env)?;
+
+    let mut last_error = None;
+
+    for env in envs {
+        let name = env.name().clone();
+        match RootPackage::<SuiFlavor>::load(path, env, modes.clone()).await {
+            Ok(root_package) => return Ok(root_package),
+            Err(err) => {
+                last_error = Some((name, err));
+            }
+        }
+    }
+
+    if let Some((name, err)) = last_error {
+        if synthetic {
+            return Err(anyhow!(
+                "package declares no environments and its dependencies could not be resolved \
+                 without one ({}); add an `[environments]` entry to its Move.toml",
+                err
+            ));
+        }
+        Err(anyhow!(
+            "unable to load dependency graph for any environment; last attempt with `{}` failed: {}",
+            name,
+            err
+        ))
+    } else {
+        Err(anyhow!(
+            "no environments available to load dependency graph at {}",
+            path.display()
+        ))
+    }
+}
+
+fn root_modules(compiled: &CompiledPackage) -> impl Iterator<Item = &CompiledModule> {
+    compiled.root_modules().map(|unit| &unit.unit.module)
+}
+
+/// `select_modules` for the root modules of `compiled`, with `--type-param-names` applied
+fn select_package_modules(args: &TreeArgs, compiled: &CompiledPackage) -> Vec<ModuleInfo> {
+    let mut modules = select_modules(&args.select, &args.detail, root_modules(compiled));
+    if args.type_param_names {
+        for module in &mut modules {
+            name_type_params(module, compiled);
+        }
+    }
+    modules
+}
+
+/// Rename the type parameters of `module`'s functions and datatypes, in their lists and in the
+/// types that use them, to the names the source map of its compiled unit records. Those without
+/// a source map entry, or with one that does not match the bytecode, keep `T0`, `T1`, ...
+fn name_type_params(module: &mut ModuleInfo, compiled: &CompiledPackage) {
+    let Some(unit) = compiled
+        .root_modules()
+        .find(|unit| unit.unit.module.name().as_str() == module.name)
+    else {
+        return;
+    };
+    let compiled_module = &unit.unit.module;
+    let source_map = &unit.unit.source_map;
+    let source_names = |names: &[SourceName], expected: usize| {
+        let names = names
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        (names.len() == expected).then_some(names)
+    };
+
+    for (index, def) in compiled_module.function_defs().iter().enumerate() {
+        let handle = compiled_module.function_handle_at(def.function);
+        let name = compiled_module.identifier_at(handle.name).as_str();
+        let Some(function) = module.functions.iter_mut().find(|f| f.name == name) else {
+            continue;
+        };
+        let Some(names) = source_map
+            .get_function_source_map(FunctionDefinitionIndex(index as TableIndex))
+            .ok()
+            .and_then(|map| source_names(&map.type_parameters, handle.type_parameters.len()))
+        else {
+            continue;
+        };
+        let format_types = |signature| {
+            compiled_module
+                .signature_at(signature)
+                .0
+                .iter()
+                .map(|token| format_named_token(compiled_module, &names, token))
+                .collect()
+        };
+        function.params = format_types(handle.parameters);
+        function.returns = format_types(handle.return_);
+        function.type_params = names;
+    }
+
+    let structs = compiled_module
+        .struct_defs()
+        .iter()
+        .enumerate()
+        .map(|(index, def)| {
+            let names = source_map
+                .get_struct_source_map(StructDefinitionIndex(index as TableIndex))
+                .map(|map| map.type_parameters.as_slice());
+            (
+                def.struct_handle,
+                names,
+                vec![def.fields().into_iter().flatten().collect::<Vec<_>>()],
+            )
+        });
+    let enums = compiled_module
+        .enum_defs()
+        .iter()
+        .enumerate()
+        .map(|(index, def)| {
+            let names = source_map
+                .get_enum_source_map(EnumDefinitionIndex(index as TableIndex))
+                .map(|map| map.type_parameters.as_slice());
+            let variants = def
+                .variants
+                .iter()
+                .map(|variant| variant.fields.iter().collect())
+                .collect();
+            (def.enum_handle, names, variants)
+        });
+    for (handle_index, names, variants) in structs.chain(enums) {
+        let handle = compiled_module.datatype_handle_at(handle_index);
+        let name = compiled_module.identifier_at(handle.name).as_str();
+        let Some(datatype) = module.structs.iter_mut().find(|d| d.name == name) else {
+            continue;
+        };
+        let Some(names) = names
+            .ok()
+            .and_then(|names| source_names(names, handle.type_parameters.len()))
+        else {
+            continue;
+        };
+
+        datatype.type_params = format_datatype_type_params(handle, &names);
+        let field_lists = if datatype.kind == "enum" {
+            datatype
+                .variants
+                .iter_mut()
+                .map(|variant| &mut variant.fields)
+                .collect::<Vec<_>>()
+        } else {
+            vec![&mut datatype.fields]
+        };
+        for (fields, defs) in field_lists.into_iter().zip(&variants) {
+            for (field, def) in fields.fields.iter_mut().zip(defs) {
+                field.type_ = format_named_token(compiled_module, &names, &def.signature.0);
+            }
+        }
+    }
+}
+
+/// The absolute path of the source file of each root module, by module name
+fn module_sources(compiled: &CompiledPackage) -> BTreeMap<String, PathBuf> {
+    compiled
+        .root_modules()
+        .map(|unit| {
+            let path = &unit.source_path;
+            let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+            (unit.unit.module.name().to_string(), path)
+        })
+        .collect()
+}
+
+/// Collect the modules to render according to `--visibility`, `--filter`, `--exclude` and
+/// `--no-empty-modules`, with the datatypes asked for by `detail`
+fn select_modules<'a>(
+    args: &SelectArgs,
+    detail: &DetailArgs,
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Vec<ModuleInfo> {
+    let compiled_modules = sorted_by_name(compiled_modules);
+    let selector = ModuleSelector::new(args, detail, &compiled_modules);
+    compiled_modules
+        .iter()
+        .filter_map(|compiled| selector.select(compiled))
+        .collect()
+}
+
+fn sorted_by_name<'a>(
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Vec<&'a CompiledModule> {
+    let mut compiled_modules = compiled_modules.collect::<Vec<_>>();
+    compiled_modules.sort_by(|a, b| a.name().cmp(b.name()));
+    compiled_modules
+}
+
+/// What selecting modules needs to know about the whole package, so that they can then be
+/// selected one at a time, as `--format stream-tree` does
+struct ModuleSelector<'a> {
+    args: &'a SelectArgs,
+    detail: &'a DetailArgs,
+    index: Option<expand::DatatypeIndex<'a>>,
+    sizes: Option<sizes::SizeIndex<'a>>,
+    caller_counts: Option<BTreeMap<calls::FunctionId, usize>>,
+}
+
+impl<'a> ModuleSelector<'a> {
+    fn new(
+        args: &'a SelectArgs,
+        detail: &'a DetailArgs,
+        compiled_modules: &[&'a CompiledModule],
+    ) -> Self {
+        ModuleSelector {
+            args,
+            detail,
+            index: detail.expand_types.then(|| {
+                expand::DatatypeIndex::new(compiled_modules).with_max_depth(detail.max_depth_types)
+            }),
+            sizes: detail
+                .struct_sizes
+                .then(|| sizes::SizeIndex::new(compiled_modules)),
+            caller_counts: detail
+                .call_counts
+                .then(|| calls::caller_counts(compiled_modules.iter().copied())),
+        }
+    }
+
+    /// `compiled` as `select_modules` shows it, or `None` when it is left out
+    fn select(&self, compiled: &CompiledModule) -> Option<ModuleInfo> {
+        let (args, detail) = (self.args, self.detail);
+        let mut module =
+            collect_modules(std::iter::once(compiled), args.visibility, args.show_init).pop()?;
+        if args.borrows_only {
+            module
+                .functions
+                .retain(|function| function.returns_reference);
+        }
+        if args.signer_only {
+            module.functions.retain(takes_signer);
+        }
+
+        if detail.structs || detail.only_objects {
+            module.structs = collect_structs(compiled);
+        }
+        if detail.only_objects {
+            module.functions.clear();
+            module
+                .structs
+                .retain(|info| info.kind == "struct" && info.abilities.has_key());
+        }
+        if detail.show_field_offsets {
+            for info in &mut module.structs {
+                number_fields(&mut info.fields);
+                for variant in &mut info.variants {
+                    number_fields(&mut variant.fields);
+                }
+            }
+        }
+        if let Some(sizes) = &self.sizes {
+            for info in &mut module.structs {
+                info.size = sizes.datatype(compiled, &info.name);
+            }
+        }
+        if detail.show_enum_tags {
+            for info in &mut module.structs {
+                for (tag, variant) in info.variants.iter_mut().enumerate() {
+                    variant.tag = Some(tag);
+                }
+            }
+        }
+        if let Some(index) = &self.index {
+            module.datatypes = index.expand_module(compiled);
+        }
+        if detail.flatten_reexports {
+            for function_def in compiled.function_defs() {
+                let handle = compiled.function_handle_at(function_def.function);
+                let name = compiled.identifier_at(handle.name).as_str();
+                if let Some(function) = module.functions.iter_mut().find(|f| f.name == name) {
+                    function.forwards_to = forward::forwarded_call(compiled, function_def);
+                }
+            }
+        }
+        if let Some(threshold) = detail.warn_large_functions {
+            for function_def in compiled.function_defs() {
+                let handle = compiled.function_handle_at(function_def.function);
+                let name = compiled.identifier_at(handle.name).as_str();
+                let instructions = instruction_count(function_def);
+                if instructions <= threshold {
+                    continue;
+                }
+                if let Some(function) = module.functions.iter_mut().find(|f| f.name == name) {
+                    function.instructions = Some(instructions);
+                }
+            }
+        }
+        if let Some(caller_counts) = &self.caller_counts {
+            let self_id = compiled.self_id();
+            for function in &mut module.functions {
+                let count = Identifier::new(function.name.as_str())
+                    .ok()
+                    .and_then(|name| caller_counts.get(&(self_id.clone(), name)));
+                function.callers = Some(count.copied().unwrap_or(0));
+            }
+        }
+
+        let module =
+            filter_modules(vec![module], args.filter.as_ref(), args.exclude.as_ref()).pop()?;
+        if detail.only_objects && module.structs.is_empty() {
+            return None;
+        }
+        if args.no_empty_modules && is_empty_module(&module) {
+            return None;
+        }
+        Some(module)
+    }
+}
+
+/// Whether `module` has nothing to show besides its name
+fn is_empty_module(module: &ModuleInfo) -> bool {
+    module.functions.is_empty() && module.structs.is_empty() && module.datatypes.is_empty()
+}
+
+/// The structs and enums defined by `module`, sorted by name
+fn collect_structs(module: &CompiledModule) -> Vec<StructInfo> {
+    let mut structs = Vec::new();
+
+    for def in module.struct_defs() {
+        let fields = def.fields().into_iter().flatten();
+        let mut info = StructInfo {
+            kind: "struct",
+            fields: collect_fields(module, fields),
+            variants: Vec::new(),
+            ..datatype_info(module, def.struct_handle)
+        };
+        info.is_otw = is_one_time_witness(module, &info);
+        structs.push(info);
+    }
+
+    for def in module.enum_defs() {
+        let variants = def
+            .variants
+            .iter()
+            .map(|variant| VariantInfo {
+                name: module.identifier_at(variant.variant_name).to_string(),
+                fields: collect_fields(module, variant.fields.iter()),
+                tag: None,
+            })
+            .collect();
+        structs.push(StructInfo {
+            kind: "enum",
+            variants,
+            ..datatype_info(module, def.enum_handle)
+        });
+    }
+
+    structs.sort_by(|a, b| a.name.cmp(&b.name));
+    structs
+}
+
+/// The parts of a `StructInfo` that come from the datatype's handle
+fn datatype_info(module: &CompiledModule, handle: DatatypeHandleIndex) -> StructInfo {
+    let handle = module.datatype_handle_at(handle);
+    let type_params = format_datatype_type_params(handle, &[]);
+
+    StructInfo {
+        kind: "struct",
+        name: module.identifier_at(handle.name).to_string(),
+        abilities: handle.abilities,
+        type_params,
+        fields: Fields {
+            positional: false,
+            fields: Vec::new(),
+        },
+        variants: Vec::new(),
+        is_otw: false,
+        size: None,
+    }
+}
+
+/// The type parameters of a datatype, as `phantom T0: store`, named after `names` (or `T0`, `T1`,
+/// ... when it is empty)
+fn format_datatype_type_params(handle: &DatatypeHandle, names: &[String]) -> Vec<String> {
+    handle
+        .type_parameters
+        .iter()
+        .enumerate()
+        .map(|(index, param)| {
+            let mut rendered = type_param_name(names, index as TypeParameterIndex);
+            if param.is_phantom {
+                rendered.insert_str(0, "phantom ");
+            }
+            if param.constraints != AbilitySet::EMPTY {
+                rendered.push_str(&format!(": {}", format_abilities(param.constraints)));
+            }
+            rendered
+        })
+        .collect()
+}
+
+/// The one-time witness convention: a struct named after its module in uppercase, with only
+/// `drop` and no type parameters or fields. Empty structs get a `bool` field named `dummy_field`
+/// in bytecode, which is allowed as well.
+fn is_one_time_witness(module: &CompiledModule, info: &StructInfo) -> bool {
+    let no_fields = match info.fields.fields.as_slice() {
+        [] => true,
+        [field] => field.name == "dummy_field" && field.type_ == "bool",
+        _ => false,
+    };
+    info.kind == "struct"
+        && info.name == module.name().as_str().to_ascii_uppercase()
+        && info.abilities == AbilitySet::singleton(Ability::Drop)
+        && info.type_params.is_empty()
+        && no_fields
+}
+
+/// The compiler lowers positional fields to `pos0`, `pos1`, ..., so a struct (or variant) is
+/// positional when its fields are named exactly that way, in order
+fn collect_fields<'a>(
+    module: &CompiledModule,
+    fields: impl Iterator<Item = &'a FieldDefinition>,
+) -> Fields {
+    let fields = fields
+        .map(|field| FieldInfo {
+            name: module.identifier_at(field.name).to_string(),
+            type_: format_signature_token(module, &field.signature.0),
+            offset: None,
+        })
+        .collect::<Vec<_>>();
+    let positional = !fields.is_empty()
+        && fields
+            .iter()
+            .enumerate()
+            .all(|(index, field)| field.name == format!("pos{}", index));
+
+    if !positional {
+        return Fields { positional, fields };
+    }
+    let fields = fields
+        .into_iter()
+        .enumerate()
+        .map(|(index, field)| FieldInfo {
+            name: index.to_string(),
+            ..field
+        })
+        .collect();
+    Fields { positional, fields }
+}
+
+fn number_fields(fields: &mut Fields) {
+    for (offset, field) in fields.fields.iter_mut().enumerate() {
+        field.offset = Some(offset);
+    }
+}
+
+/// Keep the modules matching `filter` whole and only the matching functions of the others, then
+/// drop whatever matches `exclude`. Modules that end up with no functions are dropped, unless
+/// they had none to begin with.
+fn filter_modules(
+    modules: Vec<ModuleInfo>,
+    filter: Option<&Regex>,
+    exclude: Option<&Regex>,
+) -> Vec<ModuleInfo> {
+    modules
+        .into_iter()
+        .filter_map(|mut module| {
+            let had_functions = !module.functions.is_empty();
+
+            if let Some(filter) = filter {
+                if !filter.is_match(&module.name) {
+                    module
+                        .functions
+                        .retain(|function| filter.is_match(&function.name));
+                    if module.functions.is_empty() {
+                        return None;
+                    }
+                }
+            }
+
+            if let Some(exclude) = exclude {
+                if exclude.is_match(&module.name) {
+                    return None;
+                }
+                module
+                    .functions
+                    .retain(|function| !exclude.is_match(&function.name));
+                if had_functions && module.functions.is_empty() {
+                    return None;
+                }
+            }
+
+            Some(module)
+        })
+        .collect()
+}
+
+/// Sui's module initializer: a private, non-generic `fun init` returning nothing, that takes a
+/// `TxContext` reference, optionally preceded by the module's one-time witness
+fn is_initializer(module: &CompiledModule, function_def: &FunctionDefinition) -> bool {
+    let handle = module.function_handle_at(function_def.function);
+    if module.identifier_at(handle.name).as_str() != "init"
+        || function_def.visibility != Visibility::Private
+        || function_def.is_entry
+        || !handle.type_parameters.is_empty()
+        || !module.signature_at(handle.return_).is_empty()
+    {
+        return false;
+    }
+
+    let names_datatype = |token: &SignatureToken, module_name: &str, type_name: &str| {
+        let SignatureToken::Datatype(datatype) = token else {
+            return false;
+        };
+        let datatype = module.datatype_handle_at(*datatype);
+        let defining_module = module.module_handle_at(datatype.module);
+        module.identifier_at(defining_module.name).as_str() == module_name
+            && module.identifier_at(datatype.name).as_str() == type_name
+    };
+    let is_context = |token: &SignatureToken| match token {
+        SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            names_datatype(inner, "tx_context", "TxContext")
+        }
+        _ => false,
+    };
+
+    let otw_name = module.name().as_str().to_ascii_uppercase();
+    match module.signature_at(handle.parameters).0.as_slice() {
+        [context] => is_context(context),
+        [witness, context] => {
+            names_datatype(witness, module.name().as_str(), &otw_name) && is_context(context)
+        }
+        _ => false,
+    }
+}
+
+/// The modules of the package with their functions sorted by name. With `show_init`, each
+/// module's initializer is included whatever `visibility` is, and comes first.
+fn collect_modules<'a>(
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+    visibility: VisibilityFilter,
+    show_init: bool,
+) -> Vec<ModuleInfo> {
+    let default = TypeNameArgs::default();
+    let names = TYPE_NAMES.get().unwrap_or(&default);
+    let mut modules = Vec::new();
+
+    for module in compiled_modules {
+        let mut functions = Vec::new();
+
+        for function_def in module.function_defs() {
+            let is_init = is_initializer(module, function_def);
+            if !visibility.includes(function_def.visibility) && !(show_init && is_init) {
+                continue;
+            }
+            if names.hides(module, function_def) {
+                continue;
+            }
+
+            let handle = module.function_handle_at(function_def.function);
+            let name = module.identifier_at(handle.name).to_string();
+            let type_params = (0..handle.type_parameters.len())
+                .map(|idx| format!("T{}", idx))
+                .collect();
+            let param_tokens = &module.signature_at(handle.parameters).0;
+            let params = param_tokens
+                .iter()
+                .map(|token| format_signature_token(module, token))
+                .collect();
+            let signer_params = param_tokens
+                .iter()
+                .enumerate()
+                .filter(|(_, token)| is_signer_token(token))
+                .map(|(index, _)| index)
+                .collect();
+            let return_tokens = &module.signature_at(handle.return_).0;
+            let returns = return_tokens
+                .iter()
+                .map(|token| format_signature_token(module, token))
+                .collect();
+            let returns_reference = return_tokens.iter().any(|token| {
+                matches!(
+                    token,
+                    SignatureToken::Reference(_) | SignatureToken::MutableReference(_)
+                )
+            });
+
+            functions.push(FunctionInfo {
+                name,
+                visibility: function_def.visibility,
+                is_entry: function_def.is_entry,
+                is_init,
+                type_params,
+                params,
+                returns,
+                forwards_to: None,
+                callers: None,
+                instructions: None,
+                returns_reference,
+                signer_params,
+            });
+        }
+
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        if show_init {
+            functions.sort_by_key(|function| !function.is_init);
+        }
+        let mut friends = module
+            .friend_decls()
+            .iter()
+            .map(|handle| module.identifier_at(handle.name).to_string())
+            .collect::<Vec<_>>();
+        friends.sort();
+        modules.push(ModuleInfo {
+            name: module.name().to_string(),
+            friends,
+            functions,
+            structs: Vec::new(),
+            datatypes: Vec::new(),
+            bytecode: None,
+            source_path: None,
+        });
+    }
+
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    modules
+}
+
+fn format_signature_token(module: &CompiledModule, token: &SignatureToken) -> String {
+    format_named_token(module, &[], token)
+}
+
+/// `format_signature_token` with type parameters named after `type_params` (`T0`, `T1`, ... past
+/// its end)
+fn format_named_token(
+    module: &CompiledModule,
+    type_params: &[String],
+    token: &SignatureToken,
+) -> String {
+    match token {
+        SignatureToken::Bool => "bool".to_string(),
+        SignatureToken::U8 => "u8".to_string(),
+        SignatureToken::U16 => "u16".to_string(),
+        SignatureToken::U32 => "u32".to_string(),
+        SignatureToken::U64 => "u64".to_string(),
+        SignatureToken::U128 => "u128".to_string(),
+        SignatureToken::U256 => "u256".to_string(),
+        SignatureToken::Address => "address".to_string(),
+        SignatureToken::Signer => "signer".to_string(),
+        SignatureToken::Vector(inner) => {
+            format!("vector<{}>", format_named_token(module, type_params, inner))
+        }
+        SignatureToken::Datatype(handle) => format_datatype(module, type_params, *handle, &[]),
+        SignatureToken::DatatypeInstantiation(inner) => {
+            format_datatype(module, type_params, inner.0, &inner.1)
+        }
+        SignatureToken::Reference(inner) => {
+            format!("&{}", format_named_token(module, type_params, inner))
+        }
+        SignatureToken::MutableReference(inner) => {
+            format!("&mut {}", format_named_token(module, type_params, inner))
+        }
+        SignatureToken::TypeParameter(index) => type_param_name(type_params, *index),
+    }
+}
+
+fn type_param_name(names: &[String], index: TypeParameterIndex) -> String {
+    names
+        .get(index as usize)
+        .cloned()
+        .unwrap_or_else(|| format!("T{}", index))
+}
+
+fn format_datatype(
+    module: &CompiledModule,
+    type_params: &[String],
+    handle: DatatypeHandleIndex,
+    type_args: &[SignatureToken],
+) -> String {
+    let handle = module.datatype_handle_at(handle);
+    let module_handle = module.module_handle_at(handle.module);
+    let module_name = module.identifier_at(module_handle.name).to_string();
+    let type_name = module.identifier_at(handle.name).to_string();
+    let is_self = handle.module == module.self_handle_idx();
+    let default = TypeNameArgs::default();
+    let names = TYPE_NAMES.get().unwrap_or(&default);
+    let address = module.address_identifier_at(module_handle.address);
+    let excluded = names.exclude_address.contains(address);
+
+    let mut name = if excluded || (is_self && !names.always_qualify) {
+        type_name
+    } else {
+        format!("{}::{}", module_name, type_name)
+    };
+    if names.qualify_external && !excluded && module_handle.address != module.self_handle().address
+    {
+        name = format!("0x{}::{}", address.short_str_lossless(), name);
+    }
+
+    if !type_args.is_empty() {
+        let args = type_args
+            .iter()
+            .map(|token| format_named_token(module, type_params, token))
+            .collect::<Vec<_>>()
+            .join(", ");
+        name = format!("{}<{}>", name, args);
+    }
+
+    name
+}
+
+fn write_package_tree(
+    out: &mut impl Write,
+    branches: &Branches,
+    relative_path: Option<&str>,
+    name: &str,
+    size: Option<usize>,
+    modules: &[ModuleInfo],
+    signature: SignatureArgs,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "{}",
+        render_package_header("package", relative_path, name, size)
+    )?;
+
+    if modules.is_empty() {
+        return writeln!(out, "{}{}", branches.last, "(no modules)".dimmed());
+    }
+
+    for (module_index, module) in modules.iter().enumerate() {
+        write_module_tree(
+            out,
+            branches,
+            module,
+            module_index + 1 == modules.len(),
+            signature,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// `write_package_tree`, writing and flushing each module as soon as `modules` yields the next
+/// one (which tells whether it is the last), for `--format stream-tree`. Returns whether a module
+/// had something to show, for `--fail-if-empty`.
+fn stream_package_tree(
+    out: &mut impl Write,
+    branches: &Branches,
+    relative_path: Option<&str>,
+    name: &str,
+    size: Option<usize>,
+    modules: impl Iterator<Item = ModuleInfo>,
+    signature: SignatureArgs,
+) -> io::Result<bool> {
+    writeln!(
+        out,
+        "{}",
+        render_package_header("package", relative_path, name, size)
+    )?;
+    out.flush()?;
+
+    let mut shown = false;
+    let mut pending = None;
+    for module in modules {
+        shown |= !is_empty_module(&module);
+        if let Some(previous) = pending.replace(module) {
+            write_module_tree(out, branches, &previous, false, signature)?;
+            out.flush()?;
+        }
+    }
+    match pending {
+        Some(last) => write_module_tree(out, branches, &last, true, signature)?,
+        None => writeln!(out, "{}{}", branches.last, "(no modules)".dimmed())?,
+    }
+    out.flush()?;
+    Ok(shown)
+}
+
+fn write_module_tree(
+    out: &mut impl Write,
+    branches: &Branches,
+    module: &ModuleInfo,
+    is_last_module: bool,
+    signature: SignatureArgs,
+) -> io::Result<()> {
+    let module_prefix = branches.branch(is_last_module);
+    writeln!(out, "{}{}", module_prefix, render_module_label(module))?;
+
+    let child_prefix = branches.indent(is_last_module);
+    for (func_index, function) in module.functions.iter().enumerate() {
+        let is_last_function = func_index + 1 == module.functions.len()
+            && module.structs.is_empty()
+            && module.datatypes.is_empty();
+        let function_prefix = branches.branch(is_last_function);
+        writeln!(
+            out,
+            "{}{}{}",
+            child_prefix,
+            function_prefix,
+            render_function_line(module, function, signature)
+        )?;
+    }
+    for (struct_index, info) in module.structs.iter().enumerate() {
+        let is_last_struct =
+            struct_index + 1 == module.structs.len() && module.datatypes.is_empty();
+        writeln!(
+            out,
+            "{}{}{}",
+            child_prefix,
+            branches.branch(is_last_struct),
+            render_struct(info)
+        )?;
+    }
+    write_type_trees(out, branches, child_prefix, &module.datatypes)
+}
+
+/// The same tree as `write_package_tree`, for `--format html`
+fn package_tree_node(
+    relative_path: Option<&str>,
+    name: &str,
+    size: Option<usize>,
+    modules: &[ModuleInfo],
+    signature: SignatureArgs,
+) -> html::Node {
+    let header = render_package_header("package", relative_path, name, size);
+    if modules.is_empty() {
+        return html::Node::new(
+            header,
+            vec![html::Node::leaf("(no modules)".dimmed().to_string())],
+        );
+    }
+
+    let modules = modules
+        .iter()
+        .map(|module| {
+            let mut children = module
+                .functions
+                .iter()
+                .map(|function| html::Node::leaf(render_function_line(module, function, signature)))
+                .chain(
+                    module
+                        .structs
+                        .iter()
+                        .map(|info| html::Node::leaf(render_struct(info))),
+                )
+                .collect::<Vec<_>>();
+            children.extend(type_tree_nodes(&module.datatypes));
+            html::Node::new(render_module_label(module), children)
+        })
+        .collect();
+    html::Node::new(header, modules)
+}
+
+/// The nodes of `trees` for the HTML page, built with an explicit stack like `write_type_trees`
+fn type_tree_nodes(trees: &[expand::TypeTree]) -> Vec<html::Node> {
+    // The trees left to convert at each level, with the label of the tree they are the children
+    // of and the nodes of those already converted
+    let mut stack = vec![(trees.iter(), String::new(), Vec::new())];
+    loop {
+        let (level, _, _) = stack
+            .last_mut()
+            .expect("the roots are only popped once converted");
+        if let Some(tree) = level.next() {
+            stack.push((
+                tree.children.iter(),
+                palette().types.paint(&tree.label).to_string(),
+                Vec::new(),
+            ));
+            continue;
+        }
+
+        let (_, label, children) = stack.pop().expect("the level just looked at");
+        match stack.last_mut() {
+            Some((_, _, siblings)) => siblings.push(html::Node::new(label, children)),
+            None => return children,
+        }
+    }
+}
+
+/// `label name`, then the path of the package and its size when known: the line a report on a
+/// package starts with
+fn render_package_header(
+    label: &str,
+    relative_path: Option<&str>,
+    name: &str,
+    size: Option<usize>,
+) -> String {
+    let mut line = format!("{} {}", palette().header.paint(label), name.bold());
+    if let Some(relative) = relative_path {
+        line.push(' ');
+        line.push_str(&format!("({})", relative).dimmed().to_string());
+    }
+    if let Some(size) = size {
+        line.push(' ');
+        line.push_str(&format!("({})", format_size(size)).dimmed().to_string());
+    }
+    line
+}
+
+fn render_module_label(module: &ModuleInfo) -> String {
+    let style = palette().module;
+    let name = highlight(&module.name, style.paint(&module.name)).to_string();
+    let name = match module
+        .source_path
+        .as_deref()
+        .and_then(|path| url::Url::from_file_path(path).ok())
+    {
+        Some(url) => hyperlink(url.as_str(), &name),
+        None => name,
+    };
+    format!("{} {}", style.paint("module").bold(), name)
+}
+
+/// `text` as an OSC-8 hyperlink to `url`
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// `label` in bold reverse video when `name` matches `--highlight`
+fn highlight(name: &str, label: ColoredString) -> ColoredString {
+    match HIGHLIGHT.get() {
+        Some(pattern) if pattern.is_match(name) => label.bold().reversed(),
+        _ => label,
+    }
+}
+
+fn takes_signer(function: &FunctionInfo) -> bool {
+    !function.signer_params.is_empty()
+}
+
+/// Whether `token` is a `signer` or a reference to one
+fn is_signer_token(token: &SignatureToken) -> bool {
+    match token {
+        SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            matches!(**inner, SignatureToken::Signer)
+        }
+        token => matches!(token, SignatureToken::Signer),
+    }
+}
+
+fn render_function_line(
+    module: &ModuleInfo,
+    function: &FunctionInfo,
+    signature: SignatureArgs,
+) -> String {
+    let mut line = render_function(function, signature);
+    if function.visibility == Visibility::Friend {
+        line.push(' ');
+        line.push_str(&render_friend_scope(module).dimmed().to_string());
+    }
+    if function.is_init {
+        line.push_str(&format!(" {}", palette().initializer.paint("[init]")));
+    }
+    if function.returns_reference {
+        line.push_str(&format!(" {}", palette().badge.paint("[borrow]")));
+    }
+    if let Some(callers) = function.callers {
+        let label = match callers {
+            1 => "[1 caller]".to_string(),
+            n => format!("[{} callers]", n),
+        };
+        if callers == 0 && !function.is_entry && !function.is_init {
+            line.push_str(&format!(" {}", palette().warning.paint(&label)));
+        } else {
+            line.push_str(&format!(" {}", label.dimmed()));
+        }
+    }
+    if let Some(instructions) = function.instructions {
+        line.push_str(&format!(
+            " {}",
+            palette()
+                .badge
+                .paint(&format!("[{} instructions]", instructions))
+        ));
+    }
+    if let Some(target) = &function.forwards_to {
+        line.push_str(&format!(
+            " {}",
+            format!("-> forwards to {}", target).dimmed()
+        ));
+    }
+    line
+}
+
+fn write_type_trees(
+    out: &mut impl Write,
+    branches: &Branches,
+    prefix: &str,
+    trees: &[expand::TypeTree],
+) -> io::Result<()> {
+    // The trees left to write at each level, with the prefix of their lines
+    let mut stack = vec![(trees.iter().peekable(), prefix.to_string())];
+    while let Some((level, level_prefix)) = stack.last_mut() {
+        let Some(tree) = level.next() else {
+            stack.pop();
+            continue;
+        };
+        let is_last = level.peek().is_none();
+        writeln!(
+            out,
+            "{}{}{}",
+            level_prefix,
+            branches.branch(is_last),
+            palette().types.paint(&tree.label)
+        )?;
+        let child_prefix = format!("{}{}", level_prefix, branches.indent(is_last));
+        stack.push((tree.children.iter().peekable(), child_prefix));
+    }
+
+    Ok(())
+}
+
+/// The path displayed next to a package: relative to `--strip-prefix` when given (absolute when
+/// the package is outside of it), otherwise relative to the `path` argument it was found under.
+/// `None` when the package is the base itself.
+fn package_path_label(args: &CommonArgs, package_path: &Path) -> Option<String> {
+    let Some(prefix) = &args.strip_prefix else {
+        return relative_path_label(scan_root(args, package_path), package_path);
+    };
+
+    let absolute = std::path::absolute(package_path).unwrap_or_else(|_| package_path.into());
+    let prefix = std::path::absolute(prefix).unwrap_or_else(|_| prefix.clone());
+    if absolute.starts_with(&prefix) {
+        relative_path_label(&prefix, &absolute)
+    } else {
+        Some(slash_separated(&absolute))
+    }
+}
+
+/// The path of `package_path` relative to the scanned `root`, with `/` separators on every
+/// platform so output does not depend on the host. `None` when the package is the root itself.
+fn relative_path_label(root: &Path, package_path: &Path) -> Option<String> {
+    let relative = package_path.strip_prefix(root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+
+    Some(slash_separated(relative))
+}
+
+fn slash_separated(path: &Path) -> String {
+    let mut label = path
+        .components()
+        .filter(|component| !matches!(component, Component::RootDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if path.has_root() && !matches!(path.components().next(), Some(Component::Prefix(_))) {
+        label.insert(0, '/');
+    }
+    label
+}
+
+/// The dependencies that are only in the graph because of dev-dependencies (those restricted to
+/// the `test` mode), found by comparing the graph with the one resolved without `--profile dev`.
+/// Edges are identified by the id of the dependent package and the name of the dependency.
+struct DevDependencies {
+    edges: BTreeSet<(String, String)>,
+    /// Whether to leave them out of the tree (`--no-dev`)
+    prune: bool,
+}
+
+impl DevDependencies {
+    async fn of(path: &Path, graph: &graph_cache::Graph, args: &DepsArgs) -> Result<Self> {
+        let build = &args.common.build;
+        let mut edges = BTreeSet::new();
+        if build.profile == Profile::Dev {
+            let release = BuildArgs {
+                profile: Profile::Release,
+                ..build.clone()
+            };
+            let published = load_graph_snapshot(path, &release, args.no_cache).await?;
+            edges = dependency_edges(graph);
+            for edge in dependency_edges(&published) {
+                edges.remove(&edge);
+            }
+        }
+        Ok(DevDependencies {
+            edges,
+            prune: args.no_dev,
+        })
+    }
+
+    fn is_dev(&self, package: &graph_cache::Node, dep_name: &str) -> bool {
+        self.edges
+            .contains(&(package.id.clone(), dep_name.to_string()))
+    }
+
+    /// Whether the edge from `package` to `dep_name` is left out of the tree
+    fn hides(&self, package: &graph_cache::Node, dep_name: &str) -> bool {
+        self.prune && self.is_dev(package, dep_name)
+    }
+}
+
+/// The `source` of each package of a dependency graph, by package id, as pinned in the root
+/// package's Move.lock. The lockfile pins each environment separately and the graph does not say
+/// which one it was loaded for, so the pins are taken from the first environment that has all of
+/// the graph's packages.
+struct DependencySources {
+    sources: BTreeMap<String, toml::Value>,
+}
+
+impl DependencySources {
+    /// The sources of the packages of `graph`, from the environment of the lockfile at `path`
+    /// that pins all of them
+    fn of(path: &Path, graph: &graph_cache::Graph) -> Self {
+        let ids = graph.ids();
+        let lockfile = std::fs::read_to_string(path.join("Move.lock"))
+            .ok()
+            .and_then(|contents| toml::from_str::<toml::Value>(&contents).ok());
+        let pins = lockfile
+            .as_ref()
+            .and_then(|lockfile| lockfile.get("pinned"))
+            .and_then(toml::Value::as_table)
+            .and_then(|environments| {
+                environments
+                    .values()
+                    .filter_map(toml::Value::as_table)
+                    .find(|pins| ids.iter().all(|id| pins.contains_key(id)))
+            });
+
+        let sources = pins
+            .into_iter()
+            .flatten()
+            .filter_map(|(id, pin)| Some((id.clone(), pin.get("source")?.clone())))
+            .collect();
+        DependencySources { sources }
+    }
+
+    /// The fields of the Move.lock source of the package with `id`, as sorted `key=value` pairs
+    /// that do not depend on how the lockfile is laid out
+    fn canonical(&self, id: &str) -> Vec<String> {
+        let Some(table) = self.sources.get(id).and_then(toml::Value::as_table) else {
+            return Vec::new();
+        };
+        let mut fields = table
+            .iter()
+            .map(|(key, value)| {
+                let value = value
+                    .as_str()
+                    .map_or_else(|| value.to_string(), str::to_string);
+                format!("{}={}", key, value)
+            })
+            .collect::<Vec<_>>();
+        fields.sort();
+        fields
+    }
+
+    /// `git: host/repo@rev` (with `:subdir` when the package is not at the root of the
+    /// repository), `local: path` or `on-chain: address`, or `None` when the lockfile does not say
+    fn label(&self, package: &graph_cache::Node) -> Option<String> {
+        let source = self.sources.get(&package.id)?;
+        let field = |name: &str| source.get(name).and_then(toml::Value::as_str);
+
+        if let Some(repo) = field("git") {
+            let repo = repo
+                .strip_prefix("https://")
+                .or_else(|| repo.strip_prefix("http://"))
+                .unwrap_or(repo);
+            let repo = repo.strip_suffix(".git").unwrap_or(repo);
+            let rev = field("rev").unwrap_or_default();
+            let mut label = format!("git: {}@{}", repo, rev.get(..7).unwrap_or(rev));
+            if let Some(subdir) = field("subdir").filter(|subdir| !subdir.is_empty()) {
+                label.push_str(&format!(":{}", subdir));
+            }
+            Some(label)
+        } else if let Some(path) = field("local") {
+            Some(format!("local: {}", path))
+        } else if source.get("on-chain").is_some() {
+            let address = package.address.as_deref().unwrap_or("unpublished");
+            Some(format!("on-chain: {}", address))
+        } else {
+            None
+        }
+    }
+}
+
+fn dependency_edges(graph: &graph_cache::Graph) -> BTreeSet<(String, String)> {
+    graph
+        .packages
+        .iter()
+        .flat_map(|node| {
+            node.deps
+                .iter()
+                .map(|(dep_name, _)| (node.id.clone(), dep_name.clone()))
+        })
+        .collect()
+}
+
+fn print_dependency_graph(
+    branches: &Branches,
+    relative_path: Option<String>,
+    graph: &graph_cache::Graph,
+    dev: &DevDependencies,
+    order: &DependencyOrder,
+    sources: &DependencySources,
+    collapse: bool,
+    follow_only: Option<&str>,
+) {
+    let root_info = match follow_only {
+        None => {
+            println!("{}", render_dependency_graph_header(relative_path, graph));
+            graph.root()
+        }
+        Some(target) => {
+            let found = graph
+                .packages
+                .iter()
+                .find(|node| node.name == target || node.id == target);
+            let mut line = format!(
+                "{} {} {}",
+                palette().header.paint("deps"),
+                found.map_or(target, |node| node.name.as_str()).bold(),
+                format!("(in the graph of {})", graph.name).dimmed()
+            );
+            if let Some(relative) = relative_path {
+                line.push(' ');
+                line.push_str(&format!("({})", relative).dimmed().to_string());
+            }
+            println!("{}", line);
+
+            let Some(found) = found else {
+                println!(
+                    "{}{}",
+                    branches.last,
+                    format!("(`{}` is not in the dependency graph)", target).dimmed()
+                );
+                return;
+            };
+            found
+        }
+    };
+    if sorted_deps(graph, root_info, dev, order).is_empty() {
+        println!("{}{}", branches.last, "(no dependencies)".dimmed());
+        return;
+    }
+
+    walk_tree(
+        branches,
+        root_info,
+        |package| sorted_deps(graph, package, dev, order),
+        |(_, dep_info)| *dep_info,
+        |package| package.id.clone(),
+        collapse,
+        |package, chain, prefix, already_seen| {
+            println!(
+                "{}{}",
+                prefix,
+                render_dependency_line(package, chain, already_seen, dev, sources)
+            );
+        },
+    );
+}
+
+/// `walk_edges` for a printed tree: `visit` gets the prefix of the line of each edge, branch
+/// included, instead of its depth and position
+fn walk_tree<N, E, K: Ord>(
+    branches: &Branches,
+    root: N,
+    edges: impl Fn(&N) -> Vec<E>,
+    target: impl Fn(&E) -> N,
+    key: impl Fn(&N) -> K,
+    collapse: bool,
+    mut visit: impl FnMut(&N, &[E], &str, bool),
+) {
+    // The prefix continuing below the nodes at each depth of the walk so far
+    let mut prefixes = vec![String::new()];
+    walk_edges(
+        root,
+        edges,
+        target,
+        key,
+        collapse,
+        |node, chain, depth, is_last, already_seen| {
+            prefixes.truncate(depth + 1);
+            let prefix = format!("{}{}", prefixes[depth], branches.branch(is_last));
+            visit(node, chain, &prefix, already_seen);
+            prefixes.push(format!("{}{}", prefixes[depth], branches.indent(is_last)));
+        },
+    );
+}
+
+/// Walk the tree below `root` depth-first with an explicit stack, so that however deep the graph
+/// is, it cannot overflow the native one. `edges` lists the edges out of a node in order, and
+/// `target` is the node an edge leads to. Every edge is passed to `visit` along with the node it
+/// starts from, the depth of that node (0 for `root`), whether it is the node's last edge and
+/// whether its target was reached before, by `key`. Nodes reached before are not walked again.
+///
+/// With `collapse`, an edge to a node with a single edge is followed by that edge, and so on, and
+/// `visit` gets the whole chain at once; otherwise every chain it gets is a single edge.
+fn walk_edges<N, E, K: Ord>(
+    root: N,
+    edges: impl Fn(&N) -> Vec<E>,
+    target: impl Fn(&E) -> N,
+    key: impl Fn(&N) -> K,
+    collapse: bool,
+    mut visit: impl FnMut(&N, &[E], usize, bool, bool),
+) {
+    let mut visited = BTreeSet::new();
+    visited.insert(key(&root));
+
+    // The nodes whose edges are being walked, with their depth
+    let root_edges = edges(&root).into_iter().peekable();
+    let mut stack = vec![(root, root_edges, 0)];
+    while let Some((node, node_edges, depth)) = stack.last_mut() {
+        let Some(edge) = node_edges.next() else {
+            stack.pop();
+            continue;
+        };
+        let is_last = node_edges.peek().is_none();
+        let mut next = target(&edge);
+        let mut already_seen = !visited.insert(key(&next));
+        let mut chain = vec![edge];
+        let mut next_edges = Vec::new();
+        if !already_seen {
+            next_edges = edges(&next);
+            while collapse && next_edges.len() == 1 && !already_seen {
+                let edge = next_edges.remove(0);
+                next = target(&edge);
+                already_seen = !visited.insert(key(&next));
+                chain.push(edge);
+                next_edges = if already_seen {
+                    Vec::new()
+                } else {
+                    edges(&next)
+                };
+            }
+        }
+        visit(node, &chain, *depth, is_last, already_seen);
+
+        if !already_seen {
+            let next_depth = *depth + 1;
+            stack.push((next, next_edges.into_iter().peekable(), next_depth));
+        }
+    }
+}
+
+/// The edges `walk_edges` goes through as a tree, each one made a node by `node` from what `item`
+/// makes of it and the nodes of the edges out of its target. It is assembled without recursion
+/// too, from the depth of each edge.
+fn collect_tree<N, E, K: Ord, I, T>(
+    root: N,
+    edges: impl Fn(&N) -> Vec<E>,
+    target: impl Fn(&E) -> N,
+    key: impl Fn(&N) -> K,
+    collapse: bool,
+    mut item: impl FnMut(&N, &[E], bool) -> I,
+    node: impl Fn(I, Vec<T>) -> T,
+) -> Vec<T> {
+    let mut items = Vec::new();
+    walk_edges(
+        root,
+        edges,
+        target,
+        key,
+        collapse,
+        |from, chain, depth, _, already_seen| items.push((depth, item(from, chain, already_seen))),
+    );
+
+    // The items whose children are still being collected, innermost last, with their depth
+    let mut open: Vec<(usize, I, Vec<T>)> = Vec::new();
+    let mut roots = Vec::new();
+    let close = |open: &mut Vec<(usize, I, Vec<T>)>, roots: &mut Vec<T>| {
+        if let Some((_, pending, children)) = open.pop() {
+            let done = node(pending, children);
+            match open.last_mut() {
+                Some((_, _, siblings)) => siblings.push(done),
+                None => roots.push(done),
+            }
+        }
+    };
+    for (depth, pending) in items {
+        while open
+            .last()
+            .is_some_and(|(open_depth, _, _)| *open_depth >= depth)
+        {
+            close(&mut open, &mut roots);
+        }
+        open.push((depth, pending, Vec::new()));
+    }
+    while !open.is_empty() {
+        close(&mut open, &mut roots);
+    }
+    roots
+}
+
+/// The nodes reachable from `roots` (themselves included), each once and after every node it has
+/// an edge to, found with an explicit stack. An edge back to a node still being walked, which a
+/// dependency graph cannot have, is ignored.
+fn post_order<N, K: Ord>(
+    roots: impl IntoIterator<Item = N>,
+    edges: impl Fn(&N) -> Vec<N>,
+    key: impl Fn(&N) -> K,
+) -> Vec<N> {
+    let mut seen = BTreeSet::new();
+    let mut order = Vec::new();
+    for root in roots {
+        if !seen.insert(key(&root)) {
+            continue;
+        }
+        let root_edges = edges(&root).into_iter();
+        let mut stack = vec![(root, root_edges)];
+        while let Some((_, node_edges)) = stack.last_mut() {
+            let Some(next) = node_edges.next() else {
+                order.extend(stack.pop().map(|(node, _)| node));
+                continue;
+            };
+            if seen.insert(key(&next)) {
+                let next_edges = edges(&next).into_iter();
+                stack.push((next, next_edges));
+            }
+        }
+    }
+    order
+}
+
+fn render_dependency_graph_header(
+    relative_path: Option<String>,
+    graph: &graph_cache::Graph,
+) -> String {
+    render_package_header("deps", relative_path.as_deref(), &graph.name, None)
+}
+
+/// The line for the edge from `package` to its dependency, or for a chain of edges (each from the
+/// dependency of the one before) joined by `>`
+fn render_dependency_line(
+    package: &graph_cache::Node,
+    chain: &[(&str, &graph_cache::Node)],
+    already_seen: bool,
+    dev: &DevDependencies,
+    sources: &DependencySources,
+) -> String {
+    let mut line = palette().dependency.paint("dep").bold().to_string();
+    let mut from = package;
+    for (index, &(dep_name, dep_info)) in chain.iter().enumerate() {
+        if index == 0 {
+            line.push(' ');
+        } else {
+            line.push_str(&format!(" {} ", ">".dimmed()));
+        }
+        let label = render_dependency_label(dep_name, &dep_info.name, &dep_info.id);
+        line.push_str(&palette().dependency.paint(&label).to_string());
+        if let Some(source) = sources.label(dep_info) {
+            line.push_str(&format!(" {}", format!("[{}]", source).dimmed()));
+        }
+        if dev.is_dev(from, dep_name) {
+            line.push_str(&format!(" {}", palette().badge.paint("[dev]")));
+        }
+        from = dep_info;
+    }
+    if already_seen {
+        line.push_str(&format!(" {}", "(shared)".dimmed()));
+    }
+    line
+}
+
+/// The same tree as `print_dependency_graph`, for `--format html`
+fn dependency_graph_node(
+    relative_path: Option<String>,
+    graph: &graph_cache::Graph,
+    dev: &DevDependencies,
+    order: &DependencyOrder,
+    sources: &DependencySources,
+    collapse: bool,
+) -> html::Node {
+    let header = render_dependency_graph_header(relative_path, graph);
+    let deps = collect_tree(
+        graph.root(),
+        |package| sorted_deps(graph, package, dev, order),
+        |(_, dep_info)| *dep_info,
+        |package| package.id.clone(),
+        collapse,
+        |package, chain, already_seen| {
+            render_dependency_line(package, chain, already_seen, dev, sources)
+        },
+        html::Node::new,
+    );
+    if deps.is_empty() {
+        return html::Node::new(
+            header,
+            vec![html::Node::leaf("(no dependencies)".dimmed().to_string())],
+        );
+    }
+    html::Node::new(header, deps)
+}
+
+/// The direct dependencies of `package` shown in the tree, in `order`
+fn sorted_deps<'graph>(
+    graph: &'graph graph_cache::Graph,
+    package: &'graph graph_cache::Node,
+    dev: &DevDependencies,
+    order: &DependencyOrder,
+) -> Vec<(&'graph str, &'graph graph_cache::Node)> {
+    let mut deps = graph
+        .deps(package)
+        .filter(|(dep_name, _)| !dev.hides(package, dep_name))
+        .collect::<Vec<_>>();
+
+    deps.sort_by(|(left_name, left_info), (right_name, right_info)| {
+        order
+            .compare(left_info, right_info)
+            .then_with(|| left_name.cmp(right_name))
+            .then_with(|| left_info.id.cmp(&right_info.id))
+    });
+    deps
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SortDeps {
+    /// By dependency name, then id
+    Name,
+    /// By resolved address, which groups framework packages; unpublished packages come last
+    Address,
+    /// Dependencies with the most transitive dependencies first
+    Depth,
+}
+
+/// How the dependencies of each package are ordered in the tree, ties being broken by name then
+/// id
+struct DependencyOrder {
+    sort: SortDeps,
+    /// The number of distinct packages each package (by id) depends on, directly or not. Only
+    /// computed for `SortDeps::Depth`
+    subtree_sizes: BTreeMap<String, usize>,
+}
+
+impl DependencyOrder {
+    fn new(sort: SortDeps, graph: &graph_cache::Graph, dev: &DevDependencies) -> Self {
+        let mut subtree_sizes = BTreeMap::new();
+        if sort == SortDeps::Depth {
+            for (id, below) in reachable_packages(graph, dev) {
+                subtree_sizes.insert(id, below.len());
+            }
+        }
+        DependencyOrder {
+            sort,
+            subtree_sizes,
+        }
+    }
+
+    fn compare(&self, left: &graph_cache::Node, right: &graph_cache::Node) -> std::cmp::Ordering {
+        match self.sort {
+            SortDeps::Name => std::cmp::Ordering::Equal,
+            SortDeps::Address => {
+                let left = &left.address;
+                let right = &right.address;
+                (left.is_none(), left).cmp(&(right.is_none(), right))
+            }
+            SortDeps::Depth => {
+                let size = |node: &graph_cache::Node| self.subtree_sizes.get(&node.id).copied();
+                size(right).cmp(&size(left))
+            }
+        }
+    }
+}
+
+/// The ids of the packages each package of the graph depends on, directly or not, by id. Each
+/// package's are computed from those of its dependencies, which come before it in `post_order`.
+fn reachable_packages(
+    graph: &graph_cache::Graph,
+    dev: &DevDependencies,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let deps = |node: &graph_cache::Node| {
+        graph
+            .deps(node)
+            .filter(|(dep_name, _)| !dev.hides(node, dep_name))
+            .map(|(_, dep_info)| dep_info.id.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let mut reachable: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let ids = post_order(
+        graph.ids(),
+        |id| graph.node(id).map(deps).unwrap_or_default(),
+        |id| id.clone(),
+    );
+    for id in ids {
+        let mut below = BTreeSet::new();
+        for dep_id in graph.node(&id).map(deps).unwrap_or_default() {
+            below.extend(reachable.get(&dep_id).into_iter().flatten().cloned());
+            below.insert(dep_id);
+        }
+        reachable.insert(id, below);
+    }
+    reachable
+}
+
+/// A package in `deps --format json` (and `yaml`) output
+#[derive(Serialize)]
+struct DependencyGraphData {
+    package_name: String,
+    relative_path: Option<String>,
+    dependencies: Vec<DependencyNode>,
+}
+
+/// A dependency, with its own dependencies the first time its package is reached. Later
+/// occurrences are marked `shared` and list none, as in the tree.
+#[derive(Serialize)]
+struct DependencyNode {
+    /// The name the dependent package gives the dependency
+    name: String,
+    package: String,
+    id: String,
+    dev: bool,
+    shared: bool,
+    dependencies: Vec<DependencyNode>,
+}
+
+fn dependency_graph_data(
+    relative_path: Option<String>,
+    graph: &graph_cache::Graph,
+    dev: &DevDependencies,
+    order: &DependencyOrder,
+) -> DependencyGraphData {
+    let dependencies = collect_tree(
+        graph.root(),
+        |package| sorted_deps(graph, package, dev, order),
+        |(_, dep_info)| *dep_info,
+        |package| package.id.clone(),
+        false,
+        |package, chain, shared| {
+            let (dep_name, dep_info) = chain[0];
+            DependencyNode {
+                name: dep_name.to_string(),
+                package: dep_info.name.clone(),
+                id: dep_info.id.clone(),
+                dev: dev.is_dev(package, dep_name),
+                shared,
+                dependencies: Vec::new(),
+            }
+        },
+        |node, dependencies| DependencyNode {
+            dependencies,
+            ..node
+        },
+    );
+
+    DependencyGraphData {
+        package_name: graph.name.clone(),
+        relative_path,
+        dependencies,
+    }
+}
+
+/// `display_name`, followed by the name the dependent gives it and its `id` when they differ
+fn render_dependency_label(dep_name: &str, display_name: &str, id: &str) -> String {
+    let mut label = display_name.to_string();
+
+    if dep_name != display_name {
+        label.push_str(&format!(" ({})", dep_name));
+    }
+
+    if id != display_name {
+        label.push_str(&format!(" [{}]", id));
+    }
+
+    label
+}
+
+async fn check_private_deps(
+    args: &DepsArgs,
+    package_roots: &[PathBuf],
+    internal: &Regex,
+) -> Result<()> {
+    let branches = Branches::from_args(&args.common);
+    let mut violations = 0;
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let graph = load_graph_snapshot(root, &args.common.build, args.no_cache).await?;
+        let dev = DevDependencies::of(root, &graph, args).await?;
+        let order = DependencyOrder::new(args.sort_deps, &graph, &dev);
+        println!(
+            "{}",
+            render_dependency_graph_header(package_path_label(&args.common, root), &graph)
+        );
+
+        if internal.is_match(&graph.name) {
+            println!("{}{}", branches.last, "(internal, not checked)".dimmed());
+            continue;
+        }
+        let chains = private_dependency_chains(&graph, &dev, &order, internal);
+        if chains.is_empty() {
+            println!("{}{}", branches.last, "(no internal dependencies)".dimmed());
+            continue;
+        }
+
+        violations += chains.len();
+        for (index, chain) in chains.iter().enumerate() {
+            let Some((target, via)) = chain.split_last() else {
+                continue;
+            };
+            let mut line = format!(
+                "{}{} {}",
+                branches.branch(index + 1 == chains.len()),
+                palette().removed.paint("internal"),
+                target.bold()
+            );
+            if !via.is_empty() {
+                line.push(' ');
+                line.push_str(&format!("(via {})", via.join(" > ")).dimmed().to_string());
+            }
+            println!("{}", line);
+        }
+    }
+
+    if violations > 0 {
+        bail!(
+            "{} internal {} reachable from other packages",
+            violations,
+            if violations == 1 {
+                "package is"
+            } else {
+                "packages are"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// The names along the shortest chain of dependencies from `package` to each internal package of
+/// its graph, the internal package last. Internal packages are not walked through: the first one
+/// on a path is the one that leaks.
+fn private_dependency_chains(
+    graph: &graph_cache::Graph,
+    dev: &DevDependencies,
+    order: &DependencyOrder,
+    internal: &Regex,
+) -> Vec<Vec<String>> {
+    let root = graph.root();
+    let mut seen = BTreeSet::from([root.id.as_str()]);
+    let mut queue = VecDeque::from([(root, Vec::new())]);
+    let mut chains = Vec::new();
+    while let Some((info, chain)) = queue.pop_front() {
+        for (_, dep_info) in sorted_deps(graph, info, dev, order) {
+            if !seen.insert(dep_info.id.as_str()) {
+                continue;
+            }
+            let mut chain = chain.clone();
+            chain.push(dep_info.name.clone());
+            if internal.is_match(&dep_info.name) {
+                chains.push(chain);
+            } else {
+                queue.push_back((dep_info, chain));
+            }
+        }
+    }
+    chains
+}
+
+/// The digest of a package's resolved graph in `deps --lockfile-digest`
+#[derive(Serialize)]
+struct LockfileDigest {
+    package_name: String,
+    relative_path: Option<String>,
+    /// Hex-encoded SHA-256
+    digest: String,
+}
+
+async fn print_lockfile_digests(args: &DepsArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut digests = Vec::new();
+    for root in package_roots {
+        let graph = load_graph_snapshot(root, &args.common.build, args.no_cache).await?;
+        let sources = DependencySources::of(root, &graph);
+        digests.push(LockfileDigest {
+            package_name: graph.name.clone(),
+            relative_path: package_path_label(&args.common, root),
+            digest: lockfile_digest(&graph, &sources),
+        });
+    }
+
+    if let Some(format) = args.format.data_format(args.common.json_pretty) {
+        return format.print_all(&digests);
+    }
+
+    for digest in &digests {
+        if digests.len() == 1 {
+            println!("{}", digest.digest);
+            continue;
+        }
+        let mut line = format!("{}  {}", digest.digest, digest.package_name.bold());
+        if let Some(relative) = &digest.relative_path {
+            line.push(' ');
+            line.push_str(&format!("({})", relative).dimmed().to_string());
+        }
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+/// SHA-256 of a canonical listing of `graph`: a line per package, sorted by id, with its
+/// resolved address and source, then a line per dependency edge with the name the dependent gives
+/// the dependency and the id it resolves to
+fn lockfile_digest(graph: &graph_cache::Graph, sources: &DependencySources) -> String {
+    let mut lines = graph
+        .packages
+        .iter()
+        .map(|node| {
+            let address = node.address.as_deref().unwrap_or("unpublished");
+            let source = sources.canonical(&node.id).join(" ");
+            format!("package {} {} {}", node.id, address, source)
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.dedup();
+    let edges = graph
+        .packages
+        .iter()
+        .flat_map(|node| {
+            node.deps
+                .iter()
+                .map(|(dep_name, dep_id)| (&node.id, dep_name, dep_id))
+        })
+        .collect::<BTreeSet<_>>();
+    lines.extend(
+        edges
+            .into_iter()
+            .map(|(from, dep_name, to)| format!("edge {} {} {}", from, dep_name, to)),
+    );
+
+    Sha256::digest(lines.join("\n").as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The address `package` is published at, if it is
+fn resolved_address(
+    package: &move_package_alt::graph::PackageInfo<'_, SuiFlavor>,
+) -> Option<String> {
+    match package.named_address() {
+        NamedAddress::RootPackage(Some(id)) | NamedAddress::Defined(id) => Some(id.to_string()),
+        NamedAddress::RootPackage(None) | NamedAddress::Unpublished { .. } => None,
+    }
+}
+
+/// The graph of the package at `root`, read from the cache unless `no_cache`, or resolved and
+/// then cached
+async fn load_graph_snapshot(
+    root: &Path,
+    build: &BuildArgs,
+    no_cache: bool,
+) -> Result<graph_cache::Graph> {
+    let settings = format!("{}/{:?}", build.env.join(","), build.profile);
+    let cache = if no_cache {
+        None
+    } else {
+        graph_cache::path(root, &settings)
+    };
+    if let Some(graph) = cache.as_deref().and_then(graph_cache::read) {
+        return Ok(graph);
+    }
+
+    let root_package = load_dependency_graph(root, build)
+        .await
+        .with_context(|| format!("Failed to load dependency graph at {}", root.display()))?;
+    let graph = graph_snapshot(&root_package);
+    if let Some(path) = &cache {
+        // The cache only saves time: a read-only checkout still gets its output
+        if let Err(err) = graph_cache::write(path, &graph) {
+            eprintln!(
+                "{} the dependency graph was not cached: {:#}",
+                palette().warning.paint("warning:").bold(),
+                err
+            );
+        }
+    }
+    Ok(graph)
+}
+
+fn graph_snapshot(package: &RootPackage<SuiFlavor>) -> graph_cache::Graph {
+    let packages = package
+        .packages()
+        .iter()
+        .map(|info| graph_cache::Node {
+            id: info.id().to_string(),
+            name: info.display_name().to_string(),
+            address: resolved_address(info),
+            deps: info
+                .direct_deps()
+                .into_iter()
+                .map(|(dep_name, dep_info)| {
+                    (dep_name.as_str().to_string(), dep_info.id().to_string())
+                })
+                .collect(),
+        })
+        .collect();
+    graph_cache::Graph {
+        name: package.display_name().to_string(),
+        root_id: package.package_info().id().to_string(),
+        packages,
+    }
+}
+
+fn visibility_label(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "public",
+        Visibility::Friend => "friend",
+        Visibility::Private => "private",
+    }
+}
+
+fn serialize_visibility<S: Serializer>(
+    visibility: &Visibility,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(visibility_label(*visibility))
+}
+
+/// Describe which modules may call a `public(package)` function of `module`: the module itself
+/// plus its declared friends.
+fn render_friend_scope(module: &ModuleInfo) -> String {
+    if module.friends.is_empty() {
+        return format!("[callable from: {} only]", module.name);
+    }
+
+    format!(
+        "[callable from: {}, {}]",
+        module.name,
+        module.friends.join(", ")
+    )
+}
+
+fn render_function(function: &FunctionInfo, signature: SignatureArgs) -> String {
+    let palette = palette();
+    let name = if function.is_init {
+        palette.initializer.paint(&function.name)
+    } else {
+        palette.function.paint(&function.name)
+    };
+    let name = highlight(&function.name, name);
+    let type_params = if function.type_params.is_empty() || signature.no_params {
+        String::new()
+    } else {
+        let params = function
+            .type_params
+            .iter()
+            .map(|param| palette.types.paint(param).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("<{}>", params)
+    };
+
+    let params = function
+        .params
+        .iter()
+        .enumerate()
+        .map(|(index, param)| {
+            let painted = palette.types.paint(param);
+            if signature.show_signer_params && function.signer_params.contains(&index) {
+                painted.bold().underline().to_string()
+            } else {
+                painted.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let params = if signature.no_params {
+        String::new()
+    } else {
+        format!("({})", params)
+    };
+
+    let returns = if signature.no_returns {
+        String::new()
+    } else if function.returns.is_empty() {
+        format!(": {}", palette.returns.paint("()"))
+    } else {
+        let rendered = function
+            .returns
+            .iter()
+            .map(|ret| palette.returns.paint(ret).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if function.returns.len() == 1 {
+            format!(": {}", rendered)
+        } else {
+            format!(": ({})", rendered)
+        }
+    };
+
+    let keyword = match function.visibility {
+        Visibility::Public => "fun",
+        Visibility::Friend => "public(package) fun",
+        Visibility::Private => "private fun",
+    };
+
+    format!(
+        "{} {}{}{}{}",
+        palette.keyword.paint(keyword),
+        name,
+        type_params,
+        params,
+        returns
+    )
+}
+
+fn render_struct(info: &StructInfo) -> String {
+    let palette = palette();
+    let type_params = if info.type_params.is_empty() {
+        String::new()
+    } else {
+        let params = info
+            .type_params
+            .iter()
+            .map(|param| palette.types.paint(param).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("<{}>", params)
+    };
+
+    let body = if info.kind == "enum" {
+        let variants = info
+            .variants
+            .iter()
+            .map(|variant| {
+                let tag = match variant.tag {
+                    Some(tag) => format!("{} ", format!("[{}]", tag).dimmed()),
+                    None => String::new(),
+                };
+                format!("{}{}{}", tag, variant.name, render_fields(&variant.fields))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(" {{ {} }}", variants)
+    } else {
+        render_fields(&info.fields)
+    };
+
+    let mut line = format!(
+        "{} {}{}{}",
+        palette.keyword.paint(info.kind),
+        palette.datatype.paint(&info.name),
+        type_params,
+        body
+    );
+    if info.abilities != AbilitySet::EMPTY {
+        line.push_str(&format!(
+            " {} {}",
+            palette.keyword.paint("has"),
+            format_abilities(info.abilities)
+        ));
+    }
+    if info.is_otw {
+        line.push_str(&format!(" {}", palette.badge.paint("(OTW)")));
+    }
+    if let Some(size) = info.size {
+        line.push_str(&format!(" {}", format!("[{}]", size).dimmed()));
+    }
+    line
+}
+
+/// `(u64, address)` for positional fields, ` { fee: u64, admin: address }` for named ones. Enum
+/// variants without fields render as nothing.
+fn render_fields(fields: &Fields) -> String {
+    if fields.positional {
+        let types = fields
+            .fields
+            .iter()
+            .map(|field| {
+                format!(
+                    "{}{}",
+                    render_field_offset(field),
+                    palette().types.paint(&field.type_)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        return format!("({})", types);
+    }
+    if fields.fields.is_empty() {
+        return String::new();
+    }
+
+    let fields = fields
+        .fields
+        .iter()
+        .map(|field| {
+            format!(
+                "{}{}: {}",
+                render_field_offset(field),
+                field.name,
+                palette().types.paint(&field.type_)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(" {{ {} }}", fields)
+}
+
+fn render_field_offset(field: &FieldInfo) -> String {
+    match field.offset {
+        Some(offset) => format!("{} ", format!("[{}]", offset).dimmed()),
+        None => String::new(),
+    }
+}
+
+fn serialize_base64<S: Serializer>(
+    bytes: &Option<Vec<u8>>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serializer.serialize_some(&BASE64_STANDARD.encode(bytes)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn serialize_abilities<S: Serializer>(
+    abilities: &AbilitySet,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(abilities.into_iter().map(|ability| ability.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::{
+        empty_module, CodeUnit, FunctionDefinition, FunctionHandle, FunctionHandleIndex,
+        IdentifierIndex, ModuleHandleIndex, Signature, SignatureIndex,
+    };
+
+    use crate::test_support::add_struct;
+
+    /// A module named `name` with a public, empty function for each of `functions`
+    fn fixture_module(name: &str, functions: &[&str]) -> CompiledModule {
+        let mut module = empty_module();
+        module.identifiers[0] = Identifier::new(name).unwrap();
+
+        for function in functions {
+            module.function_handles.push(FunctionHandle {
+                module: ModuleHandleIndex(0),
+                name: IdentifierIndex(module.identifiers.len() as u16),
+                parameters: SignatureIndex(0),
+                return_: SignatureIndex(0),
+                type_parameters: vec![],
+            });
+            module.identifiers.push(Identifier::new(*function).unwrap());
+            module.function_defs.push(FunctionDefinition {
+                function: FunctionHandleIndex(module.function_handles.len() as u16 - 1),
+                visibility: Visibility::Public,
+                is_entry: false,
+                acquires_global_resources: vec![],
+                code: Some(CodeUnit {
+                    locals: SignatureIndex(0),
+                    code: vec![],
+                    jump_tables: vec![],
+                }),
+            });
+        }
+
+        module
+    }
+
+    #[test]
+    fn deep_dependency_chains_are_walked_without_recursion() {
+        const DEPTH: usize = 500;
+        let branches = Branches::new(Charset::Ascii, 4);
+
+        // 0 -> 1 -> ... -> DEPTH, where every package also depends on 0 again
+        let edges = |&package: &usize| {
+            if package < DEPTH {
+                vec![package + 1, 0]
+            } else {
+                vec![]
+            }
+        };
+        let mut lines = Vec::new();
+        walk_tree(
+            &branches,
+            0,
+            edges,
+            |&dep| dep,
+            |&package| package,
+            false,
+            |&package, chain, prefix, already_seen| {
+                lines.push((package, chain[0], prefix.len(), already_seen));
+            },
+        );
+
+        assert_eq!(lines.len(), 2 * DEPTH);
+        let chain = lines.iter().filter(|line| !line.3).collect::<Vec<_>>();
+        assert_eq!(chain.len(), DEPTH);
+        for (depth, &&(package, dep, prefix_len, _)) in chain.iter().enumerate() {
+            assert_eq!((package, dep), (depth, depth + 1));
+            assert_eq!(prefix_len, 4 * (depth + 1));
+        }
+        assert!(lines.iter().filter(|line| line.1 == 0).all(|line| line.3));
+
+        // The trees of `--format html` and `json`, and `--depth-report` and `--sort-deps depth`
+        struct Dep {
+            package: usize,
+            shared: bool,
+            dependencies: Vec<Dep>,
+        }
+        let tree = collect_tree(
+            0,
+            edges,
+            |&dep| dep,
+            |&package| package,
+            false,
+            |_, chain, shared| (chain[0], shared),
+            |(package, shared), dependencies| Dep {
+                package,
+                shared,
+                dependencies,
+            },
+        );
+        let mut level = &tree;
+        for depth in 0..DEPTH {
+            assert_eq!(level.len(), 2);
+            assert_eq!((level[0].package, level[0].shared), (depth + 1, false));
+            assert_eq!((level[1].package, level[1].shared), (0, true));
+            level = &level[0].dependencies;
+        }
+        assert!(level.is_empty());
+        assert_eq!(
+            post_order([0], edges, |&package| package),
+            (0..=DEPTH).rev().collect::<Vec<_>>()
+        );
+
+        // `--expand-types` and the HTML page, for S0 { next: S1 }, ..., S{DEPTH} {}
+        let mut module = empty_module();
+        for index in 0..DEPTH {
+            let next = SignatureToken::Datatype(DatatypeHandleIndex(index as u16 + 1));
+            add_struct(&mut module, &format!("S{}", index), vec![("next", next)]);
+        }
+        add_struct(&mut module, &format!("S{}", DEPTH), vec![]);
+        let trees = expand::DatatypeIndex::new(&[&module]).expand_module(&module);
+        let root =
+            std::slice::from_ref(trees.iter().find(|tree| tree.label == "struct S0").unwrap());
+        let mut out = Vec::new();
+        write_type_trees(&mut out, &branches, "", root).unwrap();
+        let out = without_colors(&String::from_utf8(out).unwrap());
+        assert_eq!(out.lines().count(), 2 * DEPTH + 1);
+        assert!(out
+            .lines()
+            .last()
+            .unwrap()
+            .ends_with(&format!("S{}", DEPTH)));
+
+        let page = html::document("types", &type_tree_nodes(root));
+        assert_eq!(page.matches("<details open>").count(), 2 * DEPTH);
+        assert_eq!(page.matches("</details>").count(), 2 * DEPTH);
+    }
+
+    #[test]
+    fn only_narrowed_visibility_is_breaking() {
+        let mut old = fixture_module("pool", &["quote", "swap", "value"]);
+        old.function_defs[0].visibility = Visibility::Private;
+        let mut new = fixture_module("pool", &["quote", "swap", "value"]);
+        new.function_defs[1].visibility = Visibility::Friend;
+
+        let old = collect_modules(std::iter::once(&old), VisibilityFilter::All, false);
+        let new = collect_modules(std::iter::once(&new), VisibilityFilter::All, false);
+        let modules = diff::visibility_changes(&old, &new);
+
+        assert_eq!(modules.len(), 1);
+        let changes = modules[0]
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.function,
+                    change.old,
+                    change.new,
+                    change.is_breaking(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                ("quote", Visibility::Private, Visibility::Public, false),
+                ("swap", Visibility::Public, Visibility::Friend, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn reordered_type_parameters_are_not_a_change_once_normalized() {
+        // `swap<T0, T1>` with the given parameters
+        let swap = |params: Vec<SignatureToken>| {
+            let mut module = fixture_module("pool", &["swap"]);
+            module.signatures.push(Signature(params));
+            let handle = &mut module.function_handles[0];
+            handle.parameters = SignatureIndex(1);
+            handle.type_parameters = vec![AbilitySet::EMPTY; 2];
+            module
+        };
+        let old = swap(vec![
+            SignatureToken::TypeParameter(0),
+            SignatureToken::Vector(Box::new(SignatureToken::TypeParameter(1))),
+        ]);
+        let new = swap(vec![
+            SignatureToken::TypeParameter(1),
+            SignatureToken::Vector(Box::new(SignatureToken::TypeParameter(0))),
+        ]);
+        let mut old_modules = collect_modules(std::iter::once(&old), VisibilityFilter::All, false);
+        let mut new_modules = collect_modules(std::iter::once(&new), VisibilityFilter::All, false);
+        assert_eq!(
+            diff::diff_modules(&old_modules, &new_modules, false).len(),
+            1
+        );
+
+        normalize_generics(&mut old_modules, std::iter::once(&old));
+        normalize_generics(&mut new_modules, std::iter::once(&new));
+        assert_eq!(new_modules[0].functions[0].params, ["T0", "vector<T1>"]);
+        assert!(diff::diff_modules(&old_modules, &new_modules, false).is_empty());
+    }
+
+    #[test]
+    fn truncation_closes_a_cut_hyperlink() {
+        let line = format!(
+            "module {}",
+            hyperlink("file:///src/pool.move", "pool_manager")
+        );
+
+        assert_eq!(truncate::truncate_line(&line, 40), line);
+        assert_eq!(
+            truncate::truncate_line(&line, 11),
+            "module \x1b]8;;file:///src/pool.move\x1b\\poo\x1b]8;;\x1b\\…"
+        );
+    }
+
+    #[test]
+    fn single_child_chains_collapse_into_one_edge() {
+        let branches = Branches::new(Charset::Ascii, 4);
+
+        // 0 -> 1 -> 2 -> {3 -> 4, 5}
+        let mut lines = Vec::new();
+        walk_tree(
+            &branches,
+            0,
+            |&package| match package {
+                0 => vec![1],
+                1 => vec![2],
+                2 => vec![3, 5],
+                3 => vec![4],
+                _ => vec![],
+            },
+            |&dep| dep,
+            |&package| package,
+            true,
+            |&package, chain, prefix, _| lines.push((package, chain.to_vec(), prefix.len())),
+        );
+
+        assert_eq!(
+            lines,
+            vec![(0, vec![1, 2], 4), (2, vec![3, 4], 8), (2, vec![5], 8)]
+        );
+    }
+
+    #[test]
+    fn signer_params_are_recognized_behind_references() {
+        let signer = || Box::new(SignatureToken::Signer);
+        assert!(is_signer_token(&SignatureToken::Signer));
+        assert!(is_signer_token(&SignatureToken::Reference(signer())));
+        assert!(is_signer_token(&SignatureToken::MutableReference(signer())));
+        assert!(!is_signer_token(&SignatureToken::Vector(signer())));
+        assert!(!is_signer_token(&SignatureToken::MutableReference(
+            Box::new(SignatureToken::Datatype(DatatypeHandleIndex(0)))
+        )));
+    }
+}
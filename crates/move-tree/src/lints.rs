@@ -0,0 +1,399 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use move_binary_format::{
+    file_format::{DatatypeHandleIndex, FunctionDefinitionIndex, SignatureToken, Visibility},
+    CompiledModule,
+};
+use move_bytecode_source_map::source_map::SourceMap;
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use move_package_alt_compilation::compiled_package::CompiledPackage;
+
+use crate::calls;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Note,
+}
+
+/// A check that move-tree can run over a package
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    pub severity: Severity,
+}
+
+pub const DUPLICATE_ABORT_CODE: Rule = Rule {
+    id: "duplicate-abort-code",
+    description: "Two error constants in the same module share an abort code",
+    severity: Severity::Warning,
+};
+
+pub const UNUSED_PUBLIC_FUNCTION: Rule = Rule {
+    id: "unused-public-function",
+    description: "Public function is not called from any other module of its package",
+    severity: Severity::Note,
+};
+
+pub const LEAKED_INTERNAL_TYPE: Rule = Rule {
+    id: "leaked-internal-type",
+    description: "Public function signature uses a type of the package that callers outside it \
+                  cannot obtain or get rid of",
+    severity: Severity::Warning,
+};
+
+/// Every rule, in the order they are reported
+pub const RULES: &[&Rule] = &[
+    &DUPLICATE_ABORT_CODE,
+    &UNUSED_PUBLIC_FUNCTION,
+    &LEAKED_INTERNAL_TYPE,
+];
+
+/// A position in a source file (1-indexed)
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+pub struct Finding {
+    pub rule: &'static Rule,
+    pub message: String,
+    pub package: String,
+    pub module: String,
+    /// Where the finding was detected, when the package was compiled from source
+    pub location: Option<Location>,
+    /// The package manifest, used to anchor findings that have no source location
+    pub manifest: Option<PathBuf>,
+}
+
+/// The part of a module a finding is attached to, resolved to a `Location` through the source map
+enum Anchor {
+    Module,
+    Function(FunctionDefinitionIndex),
+}
+
+struct ModuleFinding {
+    /// Index of the module in the slice passed to `check`
+    module: usize,
+    rule: &'static Rule,
+    message: String,
+    anchor: Anchor,
+}
+
+/// Run every rule over the root modules of a package compiled from `package_path`
+pub fn check_compiled_package(package_path: &Path, compiled: &CompiledPackage) -> Vec<Finding> {
+    let package = compiled.compiled_package_info.package_name.as_str();
+    let units = compiled.root_modules().collect::<Vec<_>>();
+    let modules = units
+        .iter()
+        .map(|unit| (&unit.unit.module, Some(&unit.unit.source_map)))
+        .collect::<Vec<_>>();
+
+    check(&modules)
+        .into_iter()
+        .map(|finding| {
+            let unit = units[finding.module];
+            let source_map = &unit.unit.source_map;
+            let loc = match finding.anchor {
+                Anchor::Module => Some(source_map.definition_location),
+                Anchor::Function(index) => source_map
+                    .get_function_source_map(index)
+                    .ok()
+                    .map(|function| function.definition_location),
+            };
+            let location = loc
+                .and_then(|loc| compiled.file_map.start_position_opt(&loc))
+                .map(|position| Location {
+                    file: unit.source_path.clone(),
+                    line: position.user_line(),
+                    column: position.user_column(),
+                });
+
+            Finding {
+                rule: finding.rule,
+                message: finding.message,
+                package: package.to_string(),
+                module: unit.unit.module.name().to_string(),
+                location,
+                manifest: Some(package_path.join("Move.toml")),
+            }
+        })
+        .collect()
+}
+
+/// Run every rule over modules that have no sources (e.g. fetched from chain). Rules that need a
+/// source map are skipped and no finding carries a location.
+pub fn check_modules<'a>(
+    package: &str,
+    modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Vec<Finding> {
+    let modules = modules.map(|module| (module, None)).collect::<Vec<_>>();
+
+    check(&modules)
+        .into_iter()
+        .map(|finding| Finding {
+            rule: finding.rule,
+            message: finding.message,
+            package: package.to_string(),
+            module: modules[finding.module].0.name().to_string(),
+            location: None,
+            manifest: None,
+        })
+        .collect()
+}
+
+fn check(modules: &[(&CompiledModule, Option<&SourceMap>)]) -> Vec<ModuleFinding> {
+    let mut findings = Vec::new();
+
+    for (index, (module, source_map)) in modules.iter().enumerate() {
+        if let Some(source_map) = source_map {
+            check_duplicate_abort_codes(index, module, source_map, &mut findings);
+        }
+    }
+    check_unused_public_functions(modules, &mut findings);
+    check_leaked_internal_types(modules, &mut findings);
+
+    // `root_modules` follows compilation order, so sort to keep reports stable across machines
+    findings.sort_by(|a, b| {
+        (modules[a.module].0.name(), a.rule.id, &a.message).cmp(&(
+            modules[b.module].0.name(),
+            b.rule.id,
+            &b.message,
+        ))
+    });
+    findings
+}
+
+/// Error constants are `u64` constants named `E...`. The compiler deduplicates the constant pool,
+/// so two such constants with the same value end up pointing at the same pool entry.
+fn check_duplicate_abort_codes(
+    index: usize,
+    module: &CompiledModule,
+    source_map: &SourceMap,
+    findings: &mut Vec<ModuleFinding>,
+) {
+    let mut by_constant: BTreeMap<_, Vec<&str>> = BTreeMap::new();
+    for (name, constant) in &source_map.constant_map {
+        let name = name.0.as_str();
+        if name.starts_with('E') {
+            by_constant.entry(*constant).or_default().push(name);
+        }
+    }
+
+    for (constant, mut names) in by_constant {
+        let Some(constant) = module.constant_pool().get(constant as usize) else {
+            continue;
+        };
+        if names.len() < 2 || constant.type_ != SignatureToken::U64 {
+            continue;
+        }
+        let Ok(bytes) = <[u8; 8]>::try_from(constant.data.as_slice()) else {
+            continue;
+        };
+
+        names.sort();
+        findings.push(ModuleFinding {
+            module: index,
+            rule: &DUPLICATE_ABORT_CODE,
+            message: format!(
+                "abort code {} is used by {} in module `{}`",
+                u64::from_le_bytes(bytes),
+                names.join(", "),
+                module.name()
+            ),
+            anchor: Anchor::Module,
+        });
+    }
+}
+
+/// Entry functions are excluded since they are meant to be called from transactions
+fn check_unused_public_functions(
+    modules: &[(&CompiledModule, Option<&SourceMap>)],
+    findings: &mut Vec<ModuleFinding>,
+) {
+    let mut called = BTreeSet::new();
+    for (module, _) in modules {
+        let self_id = module.self_id();
+        for function_def in module.function_defs() {
+            called.extend(
+                calls::callees(module, function_def)
+                    .into_iter()
+                    .filter(|(callee, _)| *callee != self_id),
+            );
+        }
+    }
+
+    for (index, (module, _)) in modules.iter().enumerate() {
+        let self_id = module.self_id();
+        for (def_index, function_def) in module.function_defs().iter().enumerate() {
+            if function_def.visibility != Visibility::Public || function_def.is_entry {
+                continue;
+            }
+            let name = module.identifier_at(module.function_handle_at(function_def.function).name);
+            if called.contains(&(self_id.clone(), name.to_owned())) {
+                continue;
+            }
+
+            findings.push(ModuleFinding {
+                module: index,
+                rule: &UNUSED_PUBLIC_FUNCTION,
+                message: format!(
+                    "public function `{}::{}` is not called from any other module of the package",
+                    module.name(),
+                    name
+                ),
+                anchor: Anchor::Function(FunctionDefinitionIndex(def_index as u16)),
+            });
+        }
+    }
+}
+
+/// A datatype, by its defining module and name
+type DatatypeId = (ModuleId, Identifier);
+
+/// A public function can only be called from outside the package with arguments the caller can
+/// get hold of. A caller can hold a value of a package type that has `key` or `store` (it can be
+/// an object or be wrapped in one), or that some public function returns. Returned values need
+/// the reverse: a value without `key`, `store` or `drop` has to be consumed by a public function,
+/// or the caller is left with a value it cannot get rid of.
+fn check_leaked_internal_types(
+    modules: &[(&CompiledModule, Option<&SourceMap>)],
+    findings: &mut Vec<ModuleFinding>,
+) {
+    let package = modules
+        .iter()
+        .map(|(module, _)| module.self_id())
+        .collect::<BTreeSet<_>>();
+
+    let mut returned = BTreeSet::new();
+    let mut consumed = BTreeSet::new();
+    for (module, _) in modules {
+        for function_def in module.function_defs() {
+            if function_def.visibility != Visibility::Public {
+                continue;
+            }
+            let handle = module.function_handle_at(function_def.function);
+            for token in &module.signature_at(handle.return_).0 {
+                returned.extend(by_value_datatype(token).map(|h| datatype_id(module, h)));
+            }
+            for token in &module.signature_at(handle.parameters).0 {
+                consumed.extend(by_value_datatype(token).map(|h| datatype_id(module, h)));
+            }
+        }
+    }
+
+    for (index, (module, _)) in modules.iter().enumerate() {
+        for (def_index, function_def) in module.function_defs().iter().enumerate() {
+            if function_def.visibility != Visibility::Public {
+                continue;
+            }
+            let handle = module.function_handle_at(function_def.function);
+            let name = module.identifier_at(handle.name);
+
+            let mut problems = BTreeMap::new();
+            for token in &module.signature_at(handle.parameters).0 {
+                let mut used = Vec::new();
+                referenced_datatypes(token, &mut used);
+                for datatype in used {
+                    let id = datatype_id(module, datatype);
+                    let abilities = module.datatype_handle_at(datatype).abilities;
+                    if package.contains(&id.0)
+                        && !abilities.has_key()
+                        && !abilities.has_store()
+                        && !returned.contains(&id)
+                    {
+                        problems.entry(id).or_insert((
+                            "takes",
+                            "has neither `key` nor `store` and is not returned by any public \
+                             function of the package",
+                        ));
+                    }
+                }
+            }
+            for token in &module.signature_at(handle.return_).0 {
+                let Some(datatype) = by_value_datatype(token) else {
+                    continue;
+                };
+                let id = datatype_id(module, datatype);
+                let abilities = module.datatype_handle_at(datatype).abilities;
+                if package.contains(&id.0)
+                    && !abilities.has_key()
+                    && !abilities.has_store()
+                    && !abilities.has_drop()
+                    && !consumed.contains(&id)
+                {
+                    problems.entry(id).or_insert((
+                        "returns",
+                        "cannot be stored or dropped and is not taken by any public function of \
+                         the package",
+                    ));
+                }
+            }
+
+            for ((type_module, type_name), (verb, reason)) in problems {
+                findings.push(ModuleFinding {
+                    module: index,
+                    rule: &LEAKED_INTERNAL_TYPE,
+                    message: format!(
+                        "public function `{}::{}` {} `{}::{}`, which {}",
+                        module.name(),
+                        name,
+                        verb,
+                        type_module.name(),
+                        type_name,
+                        reason
+                    ),
+                    anchor: Anchor::Function(FunctionDefinitionIndex(def_index as u16)),
+                });
+            }
+        }
+    }
+}
+
+fn datatype_id(module: &CompiledModule, handle: DatatypeHandleIndex) -> DatatypeId {
+    let handle = module.datatype_handle_at(handle);
+    (
+        module.module_id_for_handle(module.module_handle_at(handle.module)),
+        module.identifier_at(handle.name).to_owned(),
+    )
+}
+
+/// The datatype `token` passes by value, if it is one
+fn by_value_datatype(token: &SignatureToken) -> Option<DatatypeHandleIndex> {
+    match token {
+        SignatureToken::Datatype(handle) => Some(*handle),
+        SignatureToken::DatatypeInstantiation(inner) => Some(inner.0),
+        _ => None,
+    }
+}
+
+/// Every datatype `token` mentions, through references, vectors and type arguments
+fn referenced_datatypes(token: &SignatureToken, used: &mut Vec<DatatypeHandleIndex>) {
+    match token {
+        SignatureToken::Datatype(handle) => used.push(*handle),
+        SignatureToken::DatatypeInstantiation(inner) => {
+            let (handle, type_args) = &**inner;
+            used.push(*handle);
+            for type_arg in type_args {
+                referenced_datatypes(type_arg, used);
+            }
+        }
+        SignatureToken::Vector(inner)
+        | SignatureToken::Reference(inner)
+        | SignatureToken::MutableReference(inner) => referenced_datatypes(inner, used),
+        SignatureToken::Bool
+        | SignatureToken::U8
+        | SignatureToken::U16
+        | SignatureToken::U32
+        | SignatureToken::U64
+        | SignatureToken::U128
+        | SignatureToken::U256
+        | SignatureToken::Address
+        | SignatureToken::Signer
+        | SignatureToken::TypeParameter(_) => {}
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+
+use crate::struct_graph::dot_id;
+
+/// A module, by the name of the package defining it and its own name
+type ModuleNode = (String, String);
+
+/// Which package defines each module a compilation knows about, by address and module name
+pub type ModuleOwners = BTreeMap<(AccountAddress, String), String>;
+
+/// The modules of the packages scanned and the modules they import, grouped by package
+#[derive(Default)]
+pub struct ModuleGraph {
+    clusters: BTreeMap<String, BTreeSet<String>>,
+    edges: BTreeSet<(ModuleNode, ModuleNode)>,
+}
+
+impl ModuleGraph {
+    /// Add the modules of `package` with an edge to every module each one imports. Imported
+    /// modules are put in the cluster of their package according to `owners`, or of their
+    /// address when it does not know them.
+    pub fn add<'a>(
+        &mut self,
+        package: &str,
+        modules: impl Iterator<Item = &'a CompiledModule>,
+        owners: &ModuleOwners,
+    ) {
+        for module in modules {
+            let from = (package.to_string(), module.name().to_string());
+            self.insert(&from);
+
+            for handle in module.module_handles() {
+                let address = *module.address_identifier_at(handle.address);
+                let name = module.identifier_at(handle.name).to_string();
+                if address == *module.address() && name == from.1 {
+                    continue;
+                }
+                let owner = owners
+                    .get(&(address, name.clone()))
+                    .cloned()
+                    .unwrap_or_else(|| address.to_hex_literal());
+                let to = (owner, name);
+                self.insert(&to);
+                self.edges.insert((from.clone(), to));
+            }
+        }
+    }
+
+    fn insert(&mut self, (package, module): &ModuleNode) {
+        self.clusters
+            .entry(package.clone())
+            .or_default()
+            .insert(module.clone());
+    }
+
+    /// Render the graph in Graphviz DOT, with a `cluster_` subgraph per package labeled with its
+    /// name. Edges between packages are dashed.
+    pub fn render_dot(&self) -> String {
+        let mut out = String::from("digraph modules {\n    compound=true;\n");
+
+        for (index, (package, modules)) in self.clusters.iter().enumerate() {
+            out.push_str(&format!(
+                "    subgraph cluster_{} {{\n        label={};\n",
+                index,
+                dot_id(package)
+            ));
+            for module in modules {
+                out.push_str(&format!(
+                    "        {} [label={}];\n",
+                    node_id(package, module),
+                    dot_id(module)
+                ));
+            }
+            out.push_str("    }\n");
+        }
+
+        for ((from_package, from_module), (to_package, to_module)) in &self.edges {
+            let style = if from_package == to_package {
+                ""
+            } else {
+                " [style=dashed]"
+            };
+            out.push_str(&format!(
+                "    {} -> {}{};\n",
+                node_id(from_package, from_module),
+                node_id(to_package, to_module),
+                style
+            ));
+        }
+
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn node_id(package: &str, module: &str) -> String {
+    dot_id(&format!("{}::{}", package, module))
+}
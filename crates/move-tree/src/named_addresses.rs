@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use move_core_types::account_address::AccountAddress;
+use move_package_alt::graph::NamedAddress;
+use serde::Serialize;
+
+use crate::{load_dependency_graph, package_path_label, render_package_header, DepsArgs};
+
+/// A named address in `deps --resolve-named-addresses`
+#[derive(Serialize)]
+struct NamedAddressValue {
+    name: String,
+    /// The published address, or the placeholder used for an unpublished package
+    address: String,
+    published: bool,
+}
+
+#[derive(Serialize)]
+struct NamedAddresses {
+    package_name: String,
+    relative_path: Option<String>,
+    /// Sorted by name
+    addresses: Vec<NamedAddressValue>,
+}
+
+/// The named addresses of each of `package_roots`, for `--resolve-named-addresses`
+pub async fn print(args: &DepsArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut packages = Vec::new();
+    for root in package_roots {
+        let root_package = load_dependency_graph(root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to load dependency graph at {}", root.display()))?;
+        let addresses = root_package
+            .package_info()
+            .named_addresses()
+            .with_context(|| format!("Failed to resolve named addresses of {}", root.display()))?
+            .into_iter()
+            .map(|(name, address)| {
+                let (address, published) = match address {
+                    NamedAddress::RootPackage(Some(id)) | NamedAddress::Defined(id) => {
+                        (id.to_string(), true)
+                    }
+                    NamedAddress::RootPackage(None) => {
+                        (AccountAddress::ZERO.to_canonical_string(true), false)
+                    }
+                    NamedAddress::Unpublished { dummy_addr } => (dummy_addr.to_string(), false),
+                };
+                NamedAddressValue {
+                    name: name.as_str().to_string(),
+                    address,
+                    published,
+                }
+            })
+            .collect();
+        packages.push(NamedAddresses {
+            package_name: root_package.display_name().to_string(),
+            relative_path: package_path_label(&args.common, root),
+            addresses,
+        });
+    }
+
+    if let Some(format) = args.format.data_format(args.common.json_pretty) {
+        return format.print_all(&packages);
+    }
+
+    let mut first = true;
+    for package in &packages {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        if packages.len() > 1 {
+            let header = render_package_header(
+                "deps",
+                package.relative_path.as_deref(),
+                &package.package_name,
+                None,
+            );
+            println!("{}", header);
+        }
+
+        let name_width = package
+            .addresses
+            .iter()
+            .map(|address| address.name.len())
+            .max()
+            .unwrap_or(0);
+        for address in &package.addresses {
+            let mut line = format!("{:name_width$}  {}", address.name, address.address);
+            if !address.published {
+                line.push_str(&format!("  {}", "(unpublished)".dimmed()));
+            }
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
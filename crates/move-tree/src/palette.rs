@@ -0,0 +1,165 @@
+use colored::{Color, ColoredString, Colorize};
+
+/// How one kind of element of the tree is drawn
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    const PLAIN: Style = Style {
+        color: None,
+        bold: false,
+        dimmed: false,
+        italic: false,
+        underline: false,
+    };
+
+    const fn color(color: Color) -> Style {
+        Style {
+            color: Some(color),
+            ..Style::PLAIN
+        }
+    }
+
+    const fn bold(self) -> Style {
+        Style { bold: true, ..self }
+    }
+
+    const fn dimmed(self) -> Style {
+        Style {
+            dimmed: true,
+            ..self
+        }
+    }
+
+    const fn italic(self) -> Style {
+        Style {
+            italic: true,
+            ..self
+        }
+    }
+
+    const fn underline(self) -> Style {
+        Style {
+            underline: true,
+            ..self
+        }
+    }
+
+    pub fn paint(self, text: &str) -> ColoredString {
+        let mut painted = text.normal();
+        if let Some(color) = self.color {
+            painted = painted.color(color);
+        }
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        if self.italic {
+            painted = painted.italic();
+        }
+        if self.underline {
+            painted = painted.underline();
+        }
+        painted
+    }
+}
+
+/// The style of each role in the tree, so that renderers name what they draw rather than a color
+#[derive(Debug)]
+pub struct Palette {
+    /// `module` and the names of modules
+    pub module: Style,
+    /// Function names
+    pub function: Style,
+    /// The names of module initializers, and their `[init]` badge
+    pub initializer: Style,
+    /// The names of structs and enums where they are defined
+    pub datatype: Style,
+    /// Type parameters, parameter and field types, and the fields linking one datatype to another
+    pub types: Style,
+    /// Return types
+    pub returns: Style,
+    /// `fun`, `struct`, `has` and the like
+    pub keyword: Style,
+    /// `package`, `deps` and the other labels a report starts with
+    pub header: Style,
+    /// `[borrow]`, `[dev]`, `(OTW)` and the other badges after a name
+    pub badge: Style,
+    /// `dep`, `used by` and the packages they name
+    pub dependency: Style,
+    /// What a diff added
+    pub added: Style,
+    /// What a diff removed, and what fails a check
+    pub removed: Style,
+    /// What a diff changed
+    pub changed: Style,
+    /// Warnings, and what is worth a second look
+    pub warning: Style,
+    /// Notes
+    pub note: Style,
+}
+
+pub const DEFAULT: Palette = Palette {
+    module: Style::color(Color::Cyan),
+    function: Style::color(Color::Green).bold(),
+    initializer: Style::color(Color::Blue).bold(),
+    datatype: Style::color(Color::Green).bold(),
+    types: Style::color(Color::Yellow),
+    returns: Style::color(Color::Magenta),
+    keyword: Style::color(Color::BrightBlack),
+    header: Style::color(Color::Blue).bold(),
+    badge: Style::color(Color::Magenta),
+    dependency: Style::color(Color::Cyan),
+    added: Style::color(Color::Green).bold(),
+    removed: Style::color(Color::Red).bold(),
+    changed: Style::color(Color::Yellow).bold(),
+    warning: Style::color(Color::Yellow),
+    note: Style::color(Color::Cyan),
+};
+
+/// Blues and yellows, which red-green color blindness keeps apart, with the roles that would
+/// otherwise share a hue told apart by weight and slant
+pub const DEUTERANOPIA: Palette = Palette {
+    module: Style::color(Color::Blue),
+    function: Style::color(Color::Yellow).bold(),
+    initializer: Style::color(Color::BrightWhite).bold().underline(),
+    datatype: Style::color(Color::Yellow).bold(),
+    types: Style::color(Color::Cyan),
+    returns: Style::color(Color::BrightBlue).italic(),
+    keyword: Style::PLAIN.dimmed(),
+    header: Style::color(Color::Blue).bold(),
+    badge: Style::color(Color::BrightCyan),
+    dependency: Style::color(Color::Blue),
+    added: Style::color(Color::Blue).bold(),
+    removed: Style::color(Color::Yellow).bold().underline(),
+    changed: Style::color(Color::Cyan).bold(),
+    warning: Style::color(Color::Yellow),
+    note: Style::color(Color::Cyan),
+};
+
+/// No colors at all, only weight, slant and underlining
+pub const MONOCHROME: Palette = Palette {
+    module: Style::PLAIN.underline(),
+    function: Style::PLAIN.bold(),
+    initializer: Style::PLAIN.bold().underline(),
+    datatype: Style::PLAIN.bold(),
+    types: Style::PLAIN,
+    returns: Style::PLAIN.italic(),
+    keyword: Style::PLAIN.dimmed(),
+    header: Style::PLAIN.bold(),
+    badge: Style::PLAIN.italic(),
+    dependency: Style::PLAIN.underline(),
+    added: Style::PLAIN.bold(),
+    removed: Style::PLAIN.bold().underline(),
+    changed: Style::PLAIN.bold().italic(),
+    warning: Style::PLAIN.bold(),
+    note: Style::PLAIN.italic(),
+};
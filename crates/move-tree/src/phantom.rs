@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use colored::Colorize;
+use move_binary_format::{
+    file_format::{DatatypeHandleIndex, FieldDefinition, SignatureToken},
+    CompiledModule,
+};
+
+use crate::{palette, render_package_header, Branches};
+
+/// A generic struct or enum and how its fields use each of its type parameters
+pub struct GenericDatatype {
+    pub module: String,
+    pub name: String,
+    pub kind: &'static str,
+    pub params: Vec<TypeParamUsage>,
+}
+
+pub struct TypeParamUsage {
+    pub index: usize,
+    pub is_phantom: bool,
+    /// The fields (`Variant.field` for enums) whose types use the parameter outside of a phantom
+    /// position, i.e. other than as an argument for a phantom parameter of another type
+    pub used_in: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Phantom,
+    NonPhantom,
+    /// Declared phantom but used by a field, which the compiler rejects. Only possible in
+    /// bytecode that was not produced by the compiler.
+    PhantomUsed,
+    /// Not phantom but never used by a field, so it could be declared phantom
+    CouldBePhantom,
+}
+
+impl TypeParamUsage {
+    pub fn status(&self) -> Status {
+        match (self.is_phantom, self.used_in.is_empty()) {
+            (true, true) => Status::Phantom,
+            (true, false) => Status::PhantomUsed,
+            (false, true) => Status::CouldBePhantom,
+            (false, false) => Status::NonPhantom,
+        }
+    }
+}
+
+impl GenericDatatype {
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+}
+
+/// The generic datatypes of `compiled_modules`, sorted by module and name
+pub fn analyze<'a>(
+    compiled_modules: impl Iterator<Item = &'a CompiledModule>,
+) -> Vec<GenericDatatype> {
+    let mut datatypes = Vec::new();
+
+    for module in compiled_modules {
+        for def in module.struct_defs() {
+            let fields = def
+                .fields()
+                .into_iter()
+                .flatten()
+                .map(|field| (module.identifier_at(field.name).to_string(), field));
+            datatypes.extend(analyze_datatype(
+                module,
+                def.struct_handle,
+                "struct",
+                fields,
+            ));
+        }
+
+        for def in module.enum_defs() {
+            let fields = def.variants.iter().flat_map(|variant| {
+                let variant_name = module.identifier_at(variant.variant_name);
+                variant.fields.iter().map(move |field| {
+                    let name = format!("{}.{}", variant_name, module.identifier_at(field.name));
+                    (name, field)
+                })
+            });
+            datatypes.extend(analyze_datatype(module, def.enum_handle, "enum", fields));
+        }
+    }
+
+    datatypes.sort_by(|a, b| (&a.module, &a.name).cmp(&(&b.module, &b.name)));
+    datatypes
+}
+
+fn analyze_datatype<'a>(
+    module: &CompiledModule,
+    handle: DatatypeHandleIndex,
+    kind: &'static str,
+    fields: impl Iterator<Item = (String, &'a FieldDefinition)>,
+) -> Option<GenericDatatype> {
+    let handle = module.datatype_handle_at(handle);
+    if handle.type_parameters.is_empty() {
+        return None;
+    }
+
+    let mut used_in: BTreeMap<u16, Vec<String>> = BTreeMap::new();
+    for (field_name, field) in fields {
+        let mut used = Vec::new();
+        non_phantom_uses(module, &field.signature.0, &mut used);
+        for index in used {
+            let fields = used_in.entry(index).or_default();
+            if !fields.contains(&field_name) {
+                fields.push(field_name.clone());
+            }
+        }
+    }
+
+    let params = handle
+        .type_parameters
+        .iter()
+        .enumerate()
+        .map(|(index, param)| TypeParamUsage {
+            index,
+            is_phantom: param.is_phantom,
+            used_in: used_in.remove(&(index as u16)).unwrap_or_default(),
+        })
+        .collect();
+
+    Some(GenericDatatype {
+        module: module.name().to_string(),
+        name: module.identifier_at(handle.name).to_string(),
+        kind,
+        params,
+    })
+}
+
+/// Collect the type parameters `token` uses, skipping the type arguments given to phantom
+/// parameters of other datatypes
+fn non_phantom_uses(module: &CompiledModule, token: &SignatureToken, used: &mut Vec<u16>) {
+    match token {
+        SignatureToken::TypeParameter(index) => used.push(*index),
+        SignatureToken::Vector(inner)
+        | SignatureToken::Reference(inner)
+        | SignatureToken::MutableReference(inner) => non_phantom_uses(module, inner, used),
+        SignatureToken::DatatypeInstantiation(inner) => {
+            let (handle, type_args) = &**inner;
+            let params = &module.datatype_handle_at(*handle).type_parameters;
+            for (param, type_arg) in params.iter().zip(type_args) {
+                if !param.is_phantom {
+                    non_phantom_uses(module, type_arg, used);
+                }
+            }
+        }
+        SignatureToken::Bool
+        | SignatureToken::U8
+        | SignatureToken::U16
+        | SignatureToken::U32
+        | SignatureToken::U64
+        | SignatureToken::U128
+        | SignatureToken::U256
+        | SignatureToken::Address
+        | SignatureToken::Signer
+        | SignatureToken::Datatype(_) => {}
+    }
+}
+
+/// The type parameters of each of `datatypes` and whether they could be, or should be, phantom
+pub fn print_report(
+    branches: &Branches,
+    relative_path: Option<String>,
+    name: &str,
+    datatypes: &[GenericDatatype],
+) {
+    println!(
+        "{}",
+        render_package_header("package", relative_path.as_deref(), name, None)
+    );
+
+    if datatypes.is_empty() {
+        println!("{}{}", branches.last, "(no generic datatypes)".dimmed());
+        return;
+    }
+
+    for (datatype_index, datatype) in datatypes.iter().enumerate() {
+        let is_last_datatype = datatype_index + 1 == datatypes.len();
+        println!(
+            "{}{} {}",
+            branches.branch(is_last_datatype),
+            palette().keyword.paint(datatype.kind),
+            palette().datatype.paint(&datatype.qualified_name())
+        );
+
+        let child_prefix = branches.indent(is_last_datatype);
+        for (param_index, param) in datatype.params.iter().enumerate() {
+            let is_last_param = param_index + 1 == datatype.params.len();
+            let name = palette().types.paint(&format!("T{}", param.index));
+            let used_in = param.used_in.join(", ");
+            let status = match param.status() {
+                Status::Phantom => "phantom".dimmed().to_string(),
+                Status::NonPhantom => format!("used by {}", used_in),
+                Status::PhantomUsed => palette()
+                    .removed
+                    .paint(&format!("declared phantom but used by {}", used_in))
+                    .to_string(),
+                Status::CouldBePhantom => palette()
+                    .warning
+                    .paint("not used by any field, could be phantom")
+                    .to_string(),
+            };
+            println!(
+                "{}{}{}: {}",
+                child_prefix,
+                branches.branch(is_last_param),
+                name,
+                status
+            );
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+/// Environment variable holding the directory of the package being analyzed, for plugins that
+/// want to read its sources or build output. Unset for on-chain packages.
+pub const PACKAGE_PATH_VAR: &str = "MOVE_TREE_PACKAGE_PATH";
+
+/// Run the executable `plugin` with `package` serialized as JSON on its stdin. Its stdout and
+/// stderr are passed through, and a non-zero exit status is an error.
+pub fn run(plugin: &Path, package_path: Option<&Path>, package: &impl Serialize) -> Result<()> {
+    let input = serde_json::to_vec(package)?;
+
+    let mut command = Command::new(plugin);
+    command.stdin(Stdio::piped());
+    if let Some(package_path) = package_path {
+        command.env(PACKAGE_PATH_VAR, package_path);
+    }
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run plugin {}", plugin.display()))?;
+
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    // A plugin may exit without reading everything, which is not an error in itself
+    if let Err(err) = stdin.write_all(&input) {
+        if err.kind() != std::io::ErrorKind::BrokenPipe {
+            return Err(err).context("Failed to write to plugin");
+        }
+    }
+    drop(stdin);
+
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to run plugin {}", plugin.display()))?;
+    if !status.success() {
+        bail!("plugin {} exited with {}", plugin.display(), status);
+    }
+    Ok(())
+}
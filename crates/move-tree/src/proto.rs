@@ -0,0 +1,189 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use prost::Message;
+
+use crate::{visibility_label, Fields, FunctionInfo, ModuleInfo, PackageJson, StructInfo};
+
+/// The messages of `proto/move_tree.proto`, written by hand rather than generated so that building
+/// the tool does not need `protoc`
+#[derive(Clone, PartialEq, Message)]
+pub struct Packages {
+    #[prost(message, repeated, tag = "1")]
+    pub packages: Vec<Package>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Package {
+    #[prost(string, tag = "1")]
+    pub package_name: String,
+    #[prost(string, optional, tag = "2")]
+    pub relative_path: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub environment: Option<String>,
+    #[prost(message, repeated, tag = "4")]
+    pub modules: Vec<Module>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Module {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, repeated, tag = "2")]
+    pub friends: Vec<String>,
+    #[prost(message, repeated, tag = "3")]
+    pub functions: Vec<Function>,
+    #[prost(message, repeated, tag = "4")]
+    pub structs: Vec<Struct>,
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub bytecode: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Function {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub visibility: String,
+    #[prost(bool, tag = "3")]
+    pub entry: bool,
+    #[prost(bool, tag = "4")]
+    pub init: bool,
+    #[prost(string, repeated, tag = "5")]
+    pub type_params: Vec<String>,
+    #[prost(string, repeated, tag = "6")]
+    pub params: Vec<String>,
+    #[prost(string, repeated, tag = "7")]
+    pub returns: Vec<String>,
+    #[prost(string, optional, tag = "8")]
+    pub forwards_to: Option<String>,
+    #[prost(uint32, optional, tag = "9")]
+    pub callers: Option<u32>,
+    #[prost(bool, tag = "10")]
+    pub borrow: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Struct {
+    #[prost(string, tag = "1")]
+    pub kind: String,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(string, repeated, tag = "3")]
+    pub abilities: Vec<String>,
+    #[prost(string, repeated, tag = "4")]
+    pub type_params: Vec<String>,
+    #[prost(bool, tag = "5")]
+    pub positional: bool,
+    #[prost(message, repeated, tag = "6")]
+    pub fields: Vec<Field>,
+    #[prost(message, repeated, tag = "7")]
+    pub variants: Vec<Variant>,
+    #[prost(bool, tag = "8")]
+    pub one_time_witness: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Variant {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(bool, tag = "2")]
+    pub positional: bool,
+    #[prost(message, repeated, tag = "3")]
+    pub fields: Vec<Field>,
+    #[prost(uint32, optional, tag = "4")]
+    pub tag: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Field {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub r#type: String,
+    #[prost(uint32, optional, tag = "3")]
+    pub offset: Option<u32>,
+}
+
+/// Write `packages` to stdout as a single encoded `Packages` message
+pub fn print(packages: &[PackageJson]) -> Result<()> {
+    let message = Packages {
+        packages: packages.iter().map(package).collect(),
+    };
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&message.encode_to_vec())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn package(package: &PackageJson) -> Package {
+    Package {
+        package_name: package.package_name.to_string(),
+        relative_path: package.relative_path.clone(),
+        environment: package.environment.map(str::to_string),
+        modules: package.modules.iter().map(module).collect(),
+    }
+}
+
+fn module(module: &ModuleInfo) -> Module {
+    Module {
+        name: module.name.clone(),
+        friends: module.friends.clone(),
+        functions: module.functions.iter().map(function).collect(),
+        structs: module.structs.iter().map(datatype).collect(),
+        bytecode: module.bytecode.clone(),
+    }
+}
+
+fn function(function: &FunctionInfo) -> Function {
+    Function {
+        name: function.name.clone(),
+        visibility: visibility_label(function.visibility).to_string(),
+        entry: function.is_entry,
+        init: function.is_init,
+        type_params: function.type_params.clone(),
+        params: function.params.clone(),
+        returns: function.returns.clone(),
+        forwards_to: function.forwards_to.clone(),
+        callers: function.callers.map(|callers| callers as u32),
+        borrow: function.returns_reference,
+    }
+}
+
+fn datatype(datatype: &StructInfo) -> Struct {
+    Struct {
+        kind: datatype.kind.to_string(),
+        name: datatype.name.clone(),
+        abilities: datatype
+            .abilities
+            .into_iter()
+            .map(|ability| ability.to_string())
+            .collect(),
+        type_params: datatype.type_params.clone(),
+        positional: datatype.fields.positional,
+        fields: fields(&datatype.fields),
+        variants: datatype
+            .variants
+            .iter()
+            .map(|variant| Variant {
+                name: variant.name.clone(),
+                positional: variant.fields.positional,
+                fields: fields(&variant.fields),
+                tag: variant.tag.map(|tag| tag as u32),
+            })
+            .collect(),
+        one_time_witness: datatype.is_otw,
+    }
+}
+
+fn fields(fields: &Fields) -> Vec<Field> {
+    fields
+        .fields
+        .iter()
+        .map(|field| Field {
+            name: field.name.clone(),
+            r#type: field.type_.clone(),
+            offset: field.offset.map(|offset| offset as u32),
+        })
+        .collect()
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    graph_cache, load_graph_snapshot, package_path_label, render_package_header, DepsArgs,
+};
+
+/// A package of a dependency graph, as listed by `--resolve-only`
+#[derive(Serialize)]
+struct ResolvedPackage {
+    name: String,
+    id: String,
+    /// The address the package resolves to in the selected environment, `null` when unpublished
+    address: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ResolvedGraph {
+    package_name: String,
+    relative_path: Option<String>,
+    packages: Vec<ResolvedPackage>,
+}
+
+/// Every package of the graph of each of `package_roots`, for `--resolve-only`
+pub async fn print_packages(args: &DepsArgs, package_roots: &[PathBuf]) -> Result<()> {
+    let mut graphs = Vec::new();
+    for root in package_roots {
+        let graph = load_graph_snapshot(root, &args.common.build, args.no_cache).await?;
+        graphs.push(ResolvedGraph {
+            package_name: graph.name.clone(),
+            relative_path: package_path_label(&args.common, root),
+            packages: resolve_packages(&graph),
+        });
+    }
+
+    if let Some(format) = args.format.data_format(args.common.json_pretty) {
+        return format.print_all(&graphs);
+    }
+
+    let mut first = true;
+    for graph in &graphs {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        if graphs.len() > 1 {
+            let header = render_package_header(
+                "deps",
+                graph.relative_path.as_deref(),
+                &graph.package_name,
+                None,
+            );
+            println!("{}", header);
+        }
+
+        let name_width = graph
+            .packages
+            .iter()
+            .map(|package| package.name.len())
+            .max();
+        let id_width = graph.packages.iter().map(|package| package.id.len()).max();
+        for package in &graph.packages {
+            println!(
+                "{:name_width$}  {:id_width$}  {}",
+                package.name,
+                package.id,
+                package.address.as_deref().unwrap_or("unpublished"),
+                name_width = name_width.unwrap_or(0),
+                id_width = id_width.unwrap_or(0)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Every package of the graph (the root included) once, sorted by name and id
+fn resolve_packages(graph: &graph_cache::Graph) -> Vec<ResolvedPackage> {
+    let mut packages = graph
+        .packages
+        .iter()
+        .map(|node| ResolvedPackage {
+            name: node.name.clone(),
+            id: node.id.clone(),
+            address: node.address.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    packages.sort_by(|a, b| (&a.name, &a.id).cmp(&(&b.name, &b.id)));
+    packages.dedup_by(|a, b| a.id == b.id);
+    packages
+}
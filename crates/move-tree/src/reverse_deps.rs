@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use move_package_alt::package::RootPackage;
+use sui_package_alt::SuiFlavor;
+
+use crate::{
+    discover_packages, load_dependency_graph, package_path_label, palette, render_package_header,
+    walk_tree, Branches, ReverseDepsArgs,
+};
+
+/// The packages depending on `--package` in the graph of each package, for `reverse-deps`
+pub async fn render(args: &ReverseDepsArgs) -> Result<()> {
+    let package_roots = discover_packages(&args.common)?;
+    let branches = Branches::from_args(&args.common);
+
+    let mut first = true;
+    for root in package_roots {
+        if !first {
+            println!();
+        }
+        first = false;
+
+        let root_package = load_dependency_graph(&root, &args.common.build)
+            .await
+            .with_context(|| format!("Failed to load dependency graph at {}", root.display()))?;
+        print_graph(
+            &branches,
+            package_path_label(&args.common, &root),
+            &root_package,
+            &args.package,
+        );
+    }
+
+    Ok(())
+}
+
+/// Print the package named (or with id) `target` in the graph of `package`, with the packages
+/// depending on it as children, up to the root package
+fn print_graph(
+    branches: &Branches,
+    relative_path: Option<String>,
+    package: &RootPackage<SuiFlavor>,
+    target: &str,
+) {
+    println!(
+        "{}",
+        render_package_header(
+            "rdeps",
+            relative_path.as_deref(),
+            package.display_name(),
+            None
+        )
+    );
+
+    let packages = package.packages();
+    let Some(target_info) = packages
+        .iter()
+        .find(|info| info.display_name() == target || info.id().as_str() == target)
+    else {
+        println!(
+            "{}{}",
+            branches.last,
+            format!("(`{}` is not in the dependency graph)", target).dimmed()
+        );
+        return;
+    };
+
+    let mut dependents: BTreeMap<String, Vec<move_package_alt::graph::PackageInfo<'_, SuiFlavor>>> =
+        BTreeMap::new();
+    for info in &packages {
+        for dep_info in info.direct_deps().into_values() {
+            dependents
+                .entry(dep_info.id().to_string())
+                .or_default()
+                .push(*info);
+        }
+    }
+    for infos in dependents.values_mut() {
+        infos.sort_by(|left, right| {
+            left.display_name()
+                .cmp(right.display_name())
+                .then_with(|| left.id().cmp(right.id()))
+        });
+    }
+
+    println!(
+        "{}{} {}",
+        branches.last,
+        palette().dependency.paint("dep").bold(),
+        palette()
+            .dependency
+            .paint(&render_package_label(target_info))
+    );
+
+    let target_id = target_info.id().to_string();
+    let prefix = branches.indent(true);
+    if !dependents.contains_key(&target_id) {
+        println!("{}{}{}", prefix, branches.last, "(no dependents)".dimmed());
+        return;
+    }
+
+    walk_tree(
+        branches,
+        target_id,
+        |id| dependents.get(id).cloned().unwrap_or_default(),
+        |info| info.id().to_string(),
+        |id| id.clone(),
+        false,
+        |_, chain, branch, already_seen| {
+            let mut line = format!(
+                "{}{}{} {}",
+                prefix,
+                branch,
+                palette().dependency.paint("used by").bold(),
+                palette().dependency.paint(&render_package_label(&chain[0]))
+            );
+            if already_seen {
+                line.push_str(&format!(" {}", "(shared)".dimmed()));
+            }
+            println!("{}", line);
+        },
+    );
+}
+
+/// A package's name, followed by its id when that differs
+fn render_package_label(package: &move_package_alt::graph::PackageInfo<'_, SuiFlavor>) -> String {
+    let display_name = package.display_name();
+    if package.id().as_str() == display_name {
+        display_name.to_string()
+    } else {
+        format!("{} [{}]", display_name, package.id())
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::lints::{Finding, Severity, RULES};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Build a SARIF 2.1.0 log with a single run holding `findings`
+pub fn report(findings: &[Finding]) -> Value {
+    let rules = RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": level(rule.severity) },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = findings.iter().map(result).collect::<Vec<_>>();
+
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "move-tree",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+fn result(finding: &Finding) -> Value {
+    let rule_index = RULES
+        .iter()
+        .position(|rule| rule.id == finding.rule.id)
+        .unwrap_or_default();
+
+    // Findings without a source position are reported against the package manifest, and always
+    // carry the package and module as logical locations
+    let physical = match (&finding.location, &finding.manifest) {
+        (Some(location), _) => Some(json!({
+            "artifactLocation": { "uri": artifact_uri(&location.file) },
+            "region": { "startLine": location.line, "startColumn": location.column },
+        })),
+        (None, Some(manifest)) => Some(json!({
+            "artifactLocation": { "uri": artifact_uri(manifest) },
+        })),
+        (None, None) => None,
+    };
+
+    let mut location = json!({
+        "logicalLocations": [
+            { "name": finding.package, "kind": "package" },
+            {
+                "name": finding.module,
+                "fullyQualifiedName": format!("{}::{}", finding.package, finding.module),
+                "kind": "module",
+            },
+        ],
+    });
+    if let Some(physical) = physical {
+        location["physicalLocation"] = physical;
+    }
+
+    json!({
+        "ruleId": finding.rule.id,
+        "ruleIndex": rule_index,
+        "level": level(finding.rule.severity),
+        "message": { "text": finding.message },
+        "locations": [location],
+    })
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    }
+}
+
+/// Code scanning resolves relative URIs against the checkout, so paths under the current
+/// directory are made relative to it
+fn artifact_uri(path: &Path) -> String {
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf());
+
+    relative.to_string_lossy().replace('\\', "/")
+}
@@ -0,0 +1,181 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, Write},
+};
+
+use anyhow::Result;
+
+use crate::{visibility_label, Fields, FunctionInfo, ModuleInfo, PackageJson, StructInfo};
+
+/// An s-expression as `read` by Emacs Lisp and other Lisps: strings for names and types, symbols
+/// for keywords and numbers
+enum Sexp {
+    Str(String),
+    Symbol(String),
+    List(Vec<Sexp>),
+}
+
+impl Display for Sexp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Sexp::Str(value) => {
+                f.write_str("\"")?;
+                for c in value.chars() {
+                    if c == '"' || c == '\\' {
+                        f.write_str("\\")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+                f.write_str("\"")
+            }
+            Sexp::Symbol(symbol) => f.write_str(symbol),
+            Sexp::List(items) => {
+                f.write_str("(")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+fn symbol(symbol: impl Into<String>) -> Sexp {
+    Sexp::Symbol(symbol.into())
+}
+
+fn string(value: impl Into<String>) -> Sexp {
+    Sexp::Str(value.into())
+}
+
+/// `(head item...)`
+fn list(head: &str, items: impl IntoIterator<Item = Sexp>) -> Sexp {
+    Sexp::List(std::iter::once(symbol(head)).chain(items).collect())
+}
+
+fn strings<'a>(values: impl IntoIterator<Item = &'a String>) -> impl Iterator<Item = Sexp> {
+    values.into_iter().map(|value| string(value.as_str()))
+}
+
+/// Write each of `packages` to stdout as a `(package ...)` form
+pub fn print(packages: &[PackageJson]) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for package in packages {
+        writeln!(stdout, "{}", render_package(package))?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// `package` as a single form, broken into a line per module and per function or datatype so
+/// that it stays readable
+fn render_package(package: &PackageJson) -> String {
+    let mut out = format!("(package {}", string(package.package_name));
+    if let Some(path) = &package.relative_path {
+        out.push_str(&format!(" {}", list("path", [string(path.as_str())])));
+    }
+    if let Some(environment) = package.environment {
+        out.push_str(&format!(" {}", list("environment", [string(environment)])));
+    }
+    for module in package.modules {
+        out.push_str(&render_module(module));
+    }
+    out.push(')');
+    out
+}
+
+fn render_module(module: &ModuleInfo) -> String {
+    let mut out = format!("\n  (module {}", string(module.name.as_str()));
+    if !module.friends.is_empty() {
+        out.push_str(&format!(" {}", list("friends", strings(&module.friends))));
+    }
+    for function in &module.functions {
+        out.push_str(&format!("\n    {}", function_form(function)));
+    }
+    for datatype in &module.structs {
+        out.push_str(&format!("\n    {}", datatype_form(datatype)));
+    }
+    out.push(')');
+    out
+}
+
+/// `(fun "mint" (visibility public) (type-params "T0") (params "u64") (returns "Coin<T0>"))`,
+/// with `(entry)`, `(init)` and `(borrow)` when they apply
+fn function_form(function: &FunctionInfo) -> Sexp {
+    let mut items = vec![
+        string(function.name.as_str()),
+        list(
+            "visibility",
+            [symbol(visibility_label(function.visibility))],
+        ),
+    ];
+    if function.is_entry {
+        items.push(list("entry", []));
+    }
+    if function.is_init {
+        items.push(list("init", []));
+    }
+    if function.returns_reference {
+        items.push(list("borrow", []));
+    }
+    items.push(list("type-params", strings(&function.type_params)));
+    items.push(list("params", strings(&function.params)));
+    items.push(list("returns", strings(&function.returns)));
+    if let Some(target) = &function.forwards_to {
+        items.push(list("forwards-to", [string(target.as_str())]));
+    }
+    if let Some(callers) = function.callers {
+        items.push(list("callers", [symbol(callers.to_string())]));
+    }
+    if let Some(instructions) = function.instructions {
+        items.push(list("instructions", [symbol(instructions.to_string())]));
+    }
+    list("fun", items)
+}
+
+/// `(struct "Coin" (abilities key store) (type-params "phantom T0") (fields ("id" "UID")))`, or
+/// `(enum ... (variants ("Some" (fields ...))))`
+fn datatype_form(datatype: &StructInfo) -> Sexp {
+    let mut items = vec![
+        string(datatype.name.as_str()),
+        list(
+            "abilities",
+            datatype
+                .abilities
+                .into_iter()
+                .map(|ability| symbol(ability.to_string())),
+        ),
+        list("type-params", strings(&datatype.type_params)),
+    ];
+    if datatype.kind == "enum" {
+        let variants = datatype.variants.iter().map(|variant| {
+            let mut items = vec![string(variant.name.as_str()), fields_form(&variant.fields)];
+            if let Some(tag) = variant.tag {
+                items.push(list("tag", [symbol(tag.to_string())]));
+            }
+            Sexp::List(items)
+        });
+        items.push(list("variants", variants));
+    } else {
+        items.push(fields_form(&datatype.fields));
+    }
+    if datatype.is_otw {
+        items.push(list("one-time-witness", []));
+    }
+    list(datatype.kind, items)
+}
+
+fn fields_form(fields: &Fields) -> Sexp {
+    list(
+        "fields",
+        fields.fields.iter().map(|field| {
+            Sexp::List(vec![
+                string(field.name.as_str()),
+                string(field.type_.as_str()),
+            ])
+        }),
+    )
+}
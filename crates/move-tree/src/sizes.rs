@@ -0,0 +1,287 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use move_binary_format::{
+    file_format::{DatatypeHandleIndex, SignatureToken},
+    CompiledModule,
+};
+use move_core_types::account_address::AccountAddress;
+use serde::Serialize;
+
+use crate::expand::{datatype_key, DatatypeKey};
+
+/// How many bytes BCS serializes a value of a type to: exactly `bytes` when it is not
+/// `variable`, and at least `bytes` otherwise
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Size {
+    pub bytes: usize,
+    pub variable: bool,
+}
+
+impl Size {
+    const fn fixed(bytes: usize) -> Self {
+        Size {
+            bytes,
+            variable: false,
+        }
+    }
+
+    /// At least `bytes`, such as a vector's length prefix
+    const fn at_least(bytes: usize) -> Self {
+        Size {
+            bytes,
+            variable: true,
+        }
+    }
+
+    fn plus(self, other: Size) -> Self {
+        Size {
+            bytes: self.bytes + other.bytes,
+            variable: self.variable || other.variable,
+        }
+    }
+}
+
+/// `48 bytes`, or `~48 bytes + variable`
+impl Display for Size {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let unit = if self.bytes == 1 { "byte" } else { "bytes" };
+        if self.variable {
+            write!(f, "~{} {} + variable", self.bytes, unit)
+        } else {
+            write!(f, "{} {}", self.bytes, unit)
+        }
+    }
+}
+
+/// The field types of a datatype: a single list for a struct, one per variant for an enum
+enum Layout<'a> {
+    Struct(Vec<&'a SignatureToken>),
+    Enum(Vec<Vec<&'a SignatureToken>>),
+}
+
+/// Types of the Move and Sui frameworks that structs commonly hold, which are not part of the
+/// package so their layout is not known otherwise
+const FRAMEWORK_SIZES: &[(AccountAddress, &str, &str, Size)] = &[
+    (AccountAddress::ONE, "ascii", "String", Size::at_least(1)),
+    (AccountAddress::ONE, "option", "Option", Size::at_least(1)),
+    (AccountAddress::ONE, "string", "String", Size::at_least(1)),
+    (AccountAddress::TWO, "bag", "Bag", Size::fixed(40)),
+    (AccountAddress::TWO, "balance", "Balance", Size::fixed(8)),
+    (AccountAddress::TWO, "balance", "Supply", Size::fixed(8)),
+    (AccountAddress::TWO, "coin", "Coin", Size::fixed(40)),
+    (AccountAddress::TWO, "object", "ID", Size::fixed(32)),
+    (AccountAddress::TWO, "object", "UID", Size::fixed(32)),
+    (
+        AccountAddress::TWO,
+        "object_bag",
+        "ObjectBag",
+        Size::fixed(40),
+    ),
+    (
+        AccountAddress::TWO,
+        "object_table",
+        "ObjectTable",
+        Size::fixed(40),
+    ),
+    (AccountAddress::TWO, "table", "Table", Size::fixed(40)),
+    (AccountAddress::TWO, "url", "Url", Size::at_least(1)),
+    (AccountAddress::TWO, "vec_map", "VecMap", Size::at_least(1)),
+    (AccountAddress::TWO, "vec_set", "VecSet", Size::at_least(1)),
+];
+
+/// Datatypes are never nested deeper than this in compiled code; past it a type is counted as
+/// variable rather than recursing further
+const MAX_DEPTH: usize = 64;
+
+/// The layouts of the datatypes defined by the modules it was built from, to estimate their
+/// serialized size. Datatypes of other packages are only known from `FRAMEWORK_SIZES`, and the
+/// others, like type parameters, are counted as variable.
+pub struct SizeIndex<'a> {
+    datatypes: BTreeMap<DatatypeKey, (&'a CompiledModule, Layout<'a>)>,
+}
+
+impl<'a> SizeIndex<'a> {
+    pub fn new(modules: &[&'a CompiledModule]) -> Self {
+        let mut datatypes = BTreeMap::new();
+
+        for &module in modules {
+            for def in module.struct_defs() {
+                let fields = def
+                    .fields()
+                    .into_iter()
+                    .flatten()
+                    .map(|field| &field.signature.0)
+                    .collect();
+                let (key, _) = datatype_key(module, def.struct_handle);
+                datatypes.insert(key, (module, Layout::Struct(fields)));
+            }
+
+            for def in module.enum_defs() {
+                let variants = def
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        variant
+                            .fields
+                            .iter()
+                            .map(|field| &field.signature.0)
+                            .collect()
+                    })
+                    .collect();
+                let (key, _) = datatype_key(module, def.enum_handle);
+                datatypes.insert(key, (module, Layout::Enum(variants)));
+            }
+        }
+
+        SizeIndex { datatypes }
+    }
+
+    /// The size of datatype `name` of `module`, with its type parameters counted as variable
+    pub fn datatype(&self, module: &CompiledModule, name: &str) -> Option<Size> {
+        let key = (
+            *module.address(),
+            module.name().to_string(),
+            name.to_string(),
+        );
+        let (module, layout) = self.datatypes.get(&key)?;
+        Some(self.layout_size(module, layout, &[], 0))
+    }
+
+    /// An enum value is its variant's tag, a ULEB128 that takes a byte for up to 127 variants,
+    /// then the variant's fields. Its size is only fixed when every variant has the same one.
+    fn layout_size(
+        &self,
+        module: &CompiledModule,
+        layout: &Layout<'_>,
+        type_args: &[Size],
+        depth: usize,
+    ) -> Size {
+        let fields_size = |fields: &[&SignatureToken]| {
+            fields
+                .iter()
+                .map(|token| self.token_size(module, token, type_args, depth))
+                .fold(Size::fixed(0), Size::plus)
+        };
+
+        match layout {
+            Layout::Struct(fields) => fields_size(fields),
+            Layout::Enum(variants) => {
+                let sizes = variants
+                    .iter()
+                    .map(|fields| fields_size(fields))
+                    .collect::<Vec<_>>();
+                let smallest = sizes.iter().map(|size| size.bytes).min().unwrap_or(0);
+                let variable = sizes
+                    .iter()
+                    .any(|size| size.variable || size.bytes != smallest);
+                Size::fixed(1).plus(Size {
+                    bytes: smallest,
+                    variable,
+                })
+            }
+        }
+    }
+
+    /// The size of `token` as used by `module`, where type parameter `i` has size `type_args[i]`
+    fn token_size(
+        &self,
+        module: &CompiledModule,
+        token: &SignatureToken,
+        type_args: &[Size],
+        depth: usize,
+    ) -> Size {
+        match token {
+            SignatureToken::Bool | SignatureToken::U8 => Size::fixed(1),
+            SignatureToken::U16 => Size::fixed(2),
+            SignatureToken::U32 => Size::fixed(4),
+            SignatureToken::U64 => Size::fixed(8),
+            SignatureToken::U128 => Size::fixed(16),
+            SignatureToken::U256 | SignatureToken::Address | SignatureToken::Signer => {
+                Size::fixed(32)
+            }
+            // A ULEB128 length, then the elements
+            SignatureToken::Vector(_) => Size::at_least(1),
+            SignatureToken::TypeParameter(index) => type_args
+                .get(*index as usize)
+                .copied()
+                .unwrap_or(Size::at_least(0)),
+            SignatureToken::Datatype(handle) => self.handle_size(module, *handle, &[], depth),
+            SignatureToken::DatatypeInstantiation(inner) => {
+                let (handle, args) = &**inner;
+                let args = args
+                    .iter()
+                    .map(|arg| self.token_size(module, arg, type_args, depth))
+                    .collect::<Vec<_>>();
+                self.handle_size(module, *handle, &args, depth)
+            }
+            SignatureToken::Reference(_) | SignatureToken::MutableReference(_) => Size::at_least(0),
+        }
+    }
+
+    fn handle_size(
+        &self,
+        module: &CompiledModule,
+        handle: DatatypeHandleIndex,
+        type_args: &[Size],
+        depth: usize,
+    ) -> Size {
+        let (key, _) = datatype_key(module, handle);
+        let (address, module_name, name) = &key;
+        if let Some((_, _, _, size)) = FRAMEWORK_SIZES.iter().find(|known| {
+            (known.0, known.1, known.2) == (*address, module_name.as_str(), name.as_str())
+        }) {
+            return *size;
+        }
+        match self.datatypes.get(&key) {
+            Some((module, layout)) if depth < MAX_DEPTH => {
+                self.layout_size(module, layout, type_args, depth + 1)
+            }
+            _ => Size::at_least(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::empty_module;
+
+    use crate::test_support::add_struct;
+
+    #[test]
+    fn fixed_width_fields_are_summed_and_vectors_are_variable() {
+        let mut module = empty_module();
+        add_struct(
+            &mut module,
+            "Config",
+            vec![
+                ("fee", SignatureToken::U64),
+                ("admin", SignatureToken::Address),
+            ],
+        );
+        add_struct(
+            &mut module,
+            "Registry",
+            vec![
+                ("config", SignatureToken::Datatype(DatatypeHandleIndex(0))),
+                (
+                    "names",
+                    SignatureToken::Vector(Box::new(SignatureToken::U8)),
+                ),
+                ("flag", SignatureToken::Bool),
+            ],
+        );
+        let index = SizeIndex::new(&[&module]);
+
+        let config = index.datatype(&module, "Config").unwrap();
+        assert_eq!(config, Size::fixed(40));
+        assert_eq!(config.to_string(), "40 bytes");
+
+        let registry = index.datatype(&module, "Registry").unwrap();
+        assert_eq!(registry, Size::at_least(42));
+        assert_eq!(registry.to_string(), "~42 bytes + variable");
+    }
+}
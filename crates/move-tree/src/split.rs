@@ -0,0 +1,47 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+/// The files `--split-by-package` and `--output-dir` write to, one per package and environment,
+/// named `<package>.<environment>.<extension>`, such as `pool.mainnet.json`. The environment
+/// keeps them apart from the other files of the directory, like the `index.html` of a site.
+/// Packages sharing a name and environment (such as copies of a package under several
+/// directories) get `-2`, `-3`, ... after their name, in the order they are written.
+pub struct SplitOutput {
+    dir: PathBuf,
+    extension: &'static str,
+    used: BTreeSet<String>,
+}
+
+impl SplitOutput {
+    pub fn new(dir: &Path, extension: &'static str) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(SplitOutput {
+            dir: dir.to_path_buf(),
+            extension,
+            used: BTreeSet::new(),
+        })
+    }
+
+    /// Write `contents` to the file of package `name` compiled for `environment`, and print its
+    /// path, which is returned
+    pub fn write(&mut self, name: &str, environment: &str, contents: &[u8]) -> Result<PathBuf> {
+        let mut stem = format!("{}.{}", name, environment);
+        let mut count = 1;
+        while self.used.contains(&stem) {
+            count += 1;
+            stem = format!("{}-{}.{}", name, count, environment);
+        }
+        self.used.insert(stem.clone());
+
+        let path = self.dir.join(format!("{}.{}", stem, self.extension));
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        println!("{}", path.display());
+        Ok(path)
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use colored::Colorize;
+use move_binary_format::{
+    file_format::{DatatypeHandleIndex, FieldDefinition, SignatureToken},
+    CompiledModule,
+};
+
+use crate::{palette, render_package_header, Branches, OutputFormat, TreeArgs};
+
+/// A struct or enum defined by the package. Every datatype is public in Move 2024, so this is the
+/// full set of types a package exposes.
+pub struct TypeNode {
+    pub module: String,
+    pub name: String,
+    pub kind: &'static str,
+    pub edges: Vec<TypeEdge>,
+}
+
+/// A reference from the fields of one datatype to another datatype
+pub struct TypeEdge {
+    pub module: String,
+    pub name: String,
+    /// The fields (`Variant.field` for enums) whose types mention the target
+    pub fields: Vec<String>,
+    /// Whether the target is defined in a different module than the source
+    pub cross_module: bool,
+}
+
+impl TypeNode {
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+}
+
+impl TypeEdge {
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+}
+
+/// Build the datatype graph of `compiled_modules`, with nodes sorted by module and name
+pub fn build<'a>(compiled_modules: impl Iterator<Item = &'a CompiledModule>) -> Vec<TypeNode> {
+    let mut nodes = Vec::new();
+
+    for module in compiled_modules {
+        for def in module.struct_defs() {
+            let fields = def
+                .fields()
+                .into_iter()
+                .flatten()
+                .map(|field| (module.identifier_at(field.name).to_string(), field));
+            nodes.push(build_node(module, def.struct_handle, "struct", fields));
+        }
+
+        for def in module.enum_defs() {
+            let fields = def.variants.iter().flat_map(|variant| {
+                let variant_name = module.identifier_at(variant.variant_name);
+                variant.fields.iter().map(move |field| {
+                    let name = format!("{}.{}", variant_name, module.identifier_at(field.name));
+                    (name, field)
+                })
+            });
+            nodes.push(build_node(module, def.enum_handle, "enum", fields));
+        }
+    }
+
+    nodes.sort_by(|a, b| (&a.module, &a.name).cmp(&(&b.module, &b.name)));
+    nodes
+}
+
+fn build_node<'a>(
+    module: &CompiledModule,
+    handle: DatatypeHandleIndex,
+    kind: &'static str,
+    fields: impl Iterator<Item = (String, &'a FieldDefinition)>,
+) -> TypeNode {
+    let self_handle = module.datatype_handle_at(handle);
+    let mut edges: BTreeMap<(String, String), TypeEdge> = BTreeMap::new();
+
+    for (field_name, field) in fields {
+        for token in field.signature.0.preorder_traversal() {
+            let target = match token {
+                SignatureToken::Datatype(handle) => *handle,
+                SignatureToken::DatatypeInstantiation(inner) => inner.0,
+                _ => continue,
+            };
+
+            let target_handle = module.datatype_handle_at(target);
+            let target_module = module.module_handle_at(target_handle.module);
+            let module_name = module.identifier_at(target_module.name).to_string();
+            let name = module.identifier_at(target_handle.name).to_string();
+            let edge = edges
+                .entry((module_name.clone(), name.clone()))
+                .or_insert_with(|| TypeEdge {
+                    module: module_name,
+                    name,
+                    fields: Vec::new(),
+                    cross_module: target_handle.module != self_handle.module,
+                });
+            if !edge.fields.contains(&field_name) {
+                edge.fields.push(field_name.clone());
+            }
+        }
+    }
+
+    TypeNode {
+        module: module.name().to_string(),
+        name: module.identifier_at(self_handle.name).to_string(),
+        kind,
+        edges: edges.into_values().collect(),
+    }
+}
+
+/// Render the graph in Graphviz DOT. Edges are labeled with the fields they come from, and edges
+/// crossing a module boundary are dashed and also name both modules.
+pub fn render_dot(package: &str, nodes: &[TypeNode]) -> String {
+    let mut out = format!("digraph {} {{\n", dot_id(package));
+
+    for node in nodes {
+        out.push_str(&format!(
+            "    {} [label={}];\n",
+            dot_id(&node.qualified_name()),
+            dot_id(&format!("{} {}", node.kind, node.qualified_name()))
+        ));
+    }
+
+    for node in nodes {
+        for edge in &node.edges {
+            let mut label = edge.fields.join(", ");
+            let mut attributes = String::new();
+            if edge.cross_module {
+                label.push_str(&format!("\n{} -> {}", node.module, edge.module));
+                attributes.push_str(", style=dashed");
+            }
+            out.push_str(&format!(
+                "    {} -> {} [label={}{}];\n",
+                dot_id(&node.qualified_name()),
+                dot_id(&edge.qualified_name()),
+                dot_id(&label),
+                attributes
+            ));
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+pub fn dot_id(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// The datatypes of `nodes` and the datatypes their fields refer to, or their DOT graph with
+/// `--format dot`
+pub fn print(args: &TreeArgs, relative_path: Option<String>, name: &str, nodes: &[TypeNode]) {
+    if args.format == OutputFormat::Dot {
+        print!("{}", render_dot(name, nodes));
+        return;
+    }
+
+    println!(
+        "{}",
+        render_package_header("types", relative_path.as_deref(), name, None)
+    );
+
+    let branches = Branches::from_args(&args.common);
+    if nodes.is_empty() {
+        println!("{}{}", branches.last, "(no datatypes)".dimmed());
+        return;
+    }
+
+    for (node_index, node) in nodes.iter().enumerate() {
+        let is_last_node = node_index + 1 == nodes.len();
+        let node_prefix = branches.branch(is_last_node);
+        println!(
+            "{}{} {}",
+            node_prefix,
+            palette().keyword.paint(node.kind),
+            palette().datatype.paint(&node.qualified_name())
+        );
+
+        let child_prefix = branches.indent(is_last_node);
+        for (edge_index, edge) in node.edges.iter().enumerate() {
+            let is_last_edge = edge_index + 1 == node.edges.len();
+            let edge_prefix = branches.branch(is_last_edge);
+            let mut line = format!(
+                "{}{}{} -> {}",
+                child_prefix,
+                edge_prefix,
+                palette().types.paint(&edge.fields.join(", ")),
+                palette().datatype.paint(&edge.qualified_name())
+            );
+            if edge.cross_module {
+                line.push(' ');
+                line.push_str(
+                    &format!("[{} -> {}]", node.module, edge.module)
+                        .dimmed()
+                        .to_string(),
+                );
+            }
+            println!("{}", line);
+        }
+    }
+}
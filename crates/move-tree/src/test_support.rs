@@ -0,0 +1,35 @@
+use move_binary_format::{
+    file_format::{
+        AbilitySet, DatatypeHandle, DatatypeHandleIndex, FieldDefinition, IdentifierIndex,
+        ModuleHandleIndex, SignatureToken, StructDefinition, StructFieldInformation, TypeSignature,
+    },
+    CompiledModule,
+};
+use move_core_types::identifier::Identifier;
+
+fn identifier(module: &mut CompiledModule, name: &str) -> IdentifierIndex {
+    module.identifiers.push(Identifier::new(name).unwrap());
+    IdentifierIndex(module.identifiers.len() as u16 - 1)
+}
+
+/// Declare `struct name { fields }` in `module`, with no abilities or type parameters
+pub fn add_struct(module: &mut CompiledModule, name: &str, fields: Vec<(&str, SignatureToken)>) {
+    let name = identifier(module, name);
+    module.datatype_handles.push(DatatypeHandle {
+        module: ModuleHandleIndex(0),
+        name,
+        abilities: AbilitySet::EMPTY,
+        type_parameters: vec![],
+    });
+    let fields = fields
+        .into_iter()
+        .map(|(field, token)| FieldDefinition {
+            name: identifier(module, field),
+            signature: TypeSignature(token),
+        })
+        .collect();
+    module.struct_defs.push(StructDefinition {
+        struct_handle: DatatypeHandleIndex(module.datatype_handles.len() as u16 - 1),
+        field_information: StructFieldInformation::Declared(fields),
+    });
+}
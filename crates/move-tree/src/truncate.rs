@@ -0,0 +1,191 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    str::FromStr,
+};
+
+const ELLIPSIS: char = '…';
+const RESET: &str = "\x1b[0m";
+const CLOSE_LINK: &str = "\x1b]8;;\x1b\\";
+
+/// The value of `--max-line-width`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineWidth {
+    /// The width of the terminal stdout is attached to, if any
+    Auto,
+    Columns(usize),
+}
+
+impl FromStr for LineWidth {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "auto" {
+            return Ok(LineWidth::Auto);
+        }
+        match value.parse::<usize>() {
+            Ok(columns) if columns > 0 => Ok(LineWidth::Columns(columns)),
+            _ => Err(format!(
+                "expected a positive number or `auto`, got `{}`",
+                value
+            )),
+        }
+    }
+}
+
+impl LineWidth {
+    /// The number of columns to cut lines at, or `None` when lines should be left alone
+    pub fn columns(self) -> Option<usize> {
+        match self {
+            LineWidth::Columns(columns) => Some(columns),
+            LineWidth::Auto => {
+                if !io::stdout().is_terminal() {
+                    return None;
+                }
+                crossterm::terminal::size()
+                    .ok()
+                    .map(|(columns, _)| columns as usize)
+                    .filter(|columns| *columns > 0)
+            }
+        }
+    }
+}
+
+/// Writes through to `inner`, cutting every line longer than `width` visible characters (ANSI
+/// escapes do not count) and ending it with `…`. Partial lines are held back until their newline
+/// or the next flush.
+pub struct Truncate<W: Write> {
+    inner: W,
+    width: Option<usize>,
+    line: Vec<u8>,
+}
+
+impl<W: Write> Truncate<W> {
+    pub fn new(inner: W, width: Option<usize>) -> Self {
+        Truncate {
+            inner,
+            width,
+            line: Vec::new(),
+        }
+    }
+
+    fn write_line(&mut self) -> io::Result<()> {
+        let line = String::from_utf8_lossy(&self.line);
+        match self.width {
+            Some(width) => self
+                .inner
+                .write_all(truncate_line(&line, width).as_bytes())?,
+            None => self.inner.write_all(line.as_bytes())?,
+        }
+        self.line.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Truncate<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte == b'\n' {
+                self.write_line()?;
+                self.inner.write_all(b"\n")?;
+            } else {
+                self.line.push(byte);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            self.write_line()?;
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Truncate<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Cut `line` to `width` visible characters, the last of which becomes `…`. Escape sequences up
+/// to the cut are kept so the visible part is styled as before, and styling is reset before the
+/// ellipsis so it is never left open, and so is a hyperlink cut in the middle.
+pub fn truncate_line(line: &str, width: usize) -> String {
+    if visible_len(line) <= width {
+        return line.to_string();
+    }
+
+    let mut out = String::new();
+    let mut visible = 0;
+    let mut styled = false;
+    let mut linked = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            let start = out.len();
+            out.push(c);
+            copy_escape(&mut chars, &mut out);
+            match out[start..].strip_prefix("\x1b]8;") {
+                Some(link) => linked = !link.starts_with(';'),
+                None => styled = true,
+            }
+            continue;
+        }
+        if visible + 1 == width {
+            break;
+        }
+        out.push(c);
+        visible += 1;
+    }
+
+    if linked {
+        out.push_str(CLOSE_LINK);
+    }
+    if styled {
+        out.push_str(RESET);
+    }
+    out.push(ELLIPSIS);
+    out
+}
+
+fn visible_len(line: &str) -> usize {
+    let mut len = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            copy_escape(&mut chars, &mut String::new());
+        } else {
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Copy the rest of a CSI sequence (`ESC [ ... final`) or an OSC sequence (`ESC ] ... ST`, such
+/// as a hyperlink) following an `ESC` to `out`
+fn copy_escape(chars: &mut std::iter::Peekable<std::str::Chars<'_>>, out: &mut String) {
+    if chars.peek() == Some(&']') {
+        while let Some(c) = chars.next() {
+            out.push(c);
+            if c == '\x07' {
+                break;
+            }
+            if c == '\x1b' && chars.peek() == Some(&'\\') {
+                out.push('\\');
+                chars.next();
+                break;
+            }
+        }
+        return;
+    }
+    if chars.peek() != Some(&'[') {
+        return;
+    }
+    for c in chars.by_ref() {
+        out.push(c);
+        if c != '[' && ('\x40'..='\x7e').contains(&c) {
+            break;
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use colored::Colorize;
+use move_binary_format::file_format::Visibility;
+
+use crate::{palette, render_package_header, Branches, FunctionInfo, ModuleInfo};
+
+/// How many functions of each visibility there are, for `--count-by-visibility`. Entry
+/// functions are also counted under their visibility.
+#[derive(Clone, Copy, Default)]
+struct VisibilityCounts {
+    public: usize,
+    friend: usize,
+    private: usize,
+    entry: usize,
+}
+
+impl VisibilityCounts {
+    fn of(functions: &[FunctionInfo]) -> Self {
+        let mut counts = VisibilityCounts::default();
+        for function in functions {
+            match function.visibility {
+                Visibility::Public => counts.public += 1,
+                Visibility::Friend => counts.friend += 1,
+                Visibility::Private => counts.private += 1,
+            }
+            if function.is_entry {
+                counts.entry += 1;
+            }
+        }
+        counts
+    }
+
+    fn add(&mut self, other: VisibilityCounts) {
+        self.public += other.public;
+        self.friend += other.friend;
+        self.private += other.private;
+        self.entry += other.entry;
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "{} public, {} public(package), {} private, {} entry",
+            self.public, self.friend, self.private, self.entry
+        )
+    }
+}
+
+/// The counts of each of `modules` and their total, for `--count-by-visibility`
+pub fn print_counts(
+    branches: &Branches,
+    relative_path: Option<String>,
+    name: &str,
+    modules: &[ModuleInfo],
+) {
+    println!(
+        "{}",
+        render_package_header("package", relative_path.as_deref(), name, None)
+    );
+
+    let mut total = VisibilityCounts::default();
+    for module in modules {
+        let counts = VisibilityCounts::of(&module.functions);
+        total.add(counts);
+        println!(
+            "{}{} {}: {}",
+            branches.branch(false),
+            palette().module.paint("module").bold(),
+            palette().module.paint(&module.name),
+            counts.render()
+        );
+    }
+    println!(
+        "{}{}: {}",
+        branches.last,
+        "total".bold(),
+        total.render().bold()
+    );
+}
@@ -0,0 +1,5 @@
+module basic::admin {
+    public fun check(flag: bool) {
+        assert!(flag);
+    }
+}
@@ -0,0 +1,13 @@
+module basic::counter {
+    public fun new(): u64 {
+        0
+    }
+
+    public fun increment(value: u64): u64 {
+        value + 1
+    }
+
+    fun reset(): u64 {
+        0
+    }
+}
@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use move_tree::render_package_tree_to_string;
+
+#[tokio::test]
+async fn fixture_package_tree_matches_snapshot() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/basic");
+    let tree = render_package_tree_to_string(&path).await.unwrap();
+
+    assert_eq!(
+        tree,
+        "package basic\n\
+         |-- module admin\n\
+         |   `-- fun check(bool): ()\n\
+         `-- module counter\n    \
+             |-- fun increment(u64): u64\n    \
+             `-- fun new(): u64\n"
+    );
+}
+
+#[tokio::test]
+async fn package_tree_output_is_deterministic() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/basic");
+    let first = render_package_tree_to_string(&path).await.unwrap();
+    let second = render_package_tree_to_string(&path).await.unwrap();
+
+    assert_eq!(first.as_bytes(), second.as_bytes());
+}